@@ -3,6 +3,25 @@ pub const PLATFORM_FEE_BPS: u16 = 500;
 pub const LIQUIDITY_RESERVE_BPS: u16 = 1_500;
 pub const WINNER_POT_BPS: u16 = 3_000;
 
+/// Pause flags, shared by `ProtocolConfig.pause_flags` and `Room.pause_flags`.
+pub const PAUSE_PREDICTIONS: u8 = 1 << 0;
+pub const PAUSE_SETTLEMENT: u8 = 1 << 1;
+pub const PAUSE_CLAIMS: u8 = 1 << 2;
+pub const PAUSE_LIQUIDITY: u8 = 1 << 3;
+pub const PAUSE_ALL: u8 = PAUSE_PREDICTIONS | PAUSE_SETTLEMENT | PAUSE_CLAIMS | PAUSE_LIQUIDITY;
+
+pub const MAX_ROOM_OPERATORS: usize = 4;
+
 pub const MAX_TRACKS: u8 = 9;
 pub const MAX_STEPS: u8 = 32;
-pub const REVEAL_BITMAP_BYTES: usize = ((MAX_TRACKS as usize) * (MAX_STEPS as usize)).div_ceil(8);
+pub const TILE_COUNT: usize = (MAX_TRACKS as usize) * (MAX_STEPS as usize);
+/// Entries per batch placement; keeps the instruction inside one transaction.
+pub const MAX_BATCH_ENTRIES: usize = 32;
+/// Upper bound on `RoundShard` accounts per round; `lock_round` folds every
+/// one of them in a single transaction.
+pub const MAX_ROUND_SHARDS: u8 = 8;
+
+pub const REVEAL_BITMAP_BYTES: usize = TILE_COUNT.div_ceil(8);
+
+pub const REVEAL_COMMIT_VERSION_V1: u8 = 1;
+pub const REVEAL_COMMIT_VERSION_V2: u8 = 2;
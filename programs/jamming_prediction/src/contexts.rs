@@ -4,7 +4,11 @@ use anchor_spl::{
     token::{Mint, Token, TokenAccount},
 };
 
-use crate::{error::ErrorCode, state::*};
+use crate::{
+    error::ErrorCode,
+    params::{BuyTileSharesParams, PlacePredictionBatchParams, PlacePredictionParams},
+    state::*,
+};
 
 #[derive(Accounts)]
 pub struct InitializeProtocol<'info> {
@@ -22,7 +26,40 @@ pub struct InitializeProtocol<'info> {
 }
 
 #[derive(Accounts)]
-pub struct UpdateProtocolConfig<'info> {
+pub struct ProposeProtocolConfig<'info> {
+    #[account(mut, address = protocol.fee_manager @ ErrorCode::MissingProtocolRole)]
+    pub fee_manager: Signer<'info>,
+    #[account(seeds = [b"protocol"], bump = protocol.bump)]
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(
+        init,
+        payer = fee_manager,
+        space = PendingProtocolConfig::LEN,
+        seeds = [b"pending_config", protocol.key().as_ref()],
+        bump
+    )]
+    pub pending_config: Account<'info, PendingProtocolConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveProtocolConfig<'info> {
+    #[account(mut, address = protocol.fee_manager @ ErrorCode::MissingProtocolRole)]
+    pub fee_manager: Signer<'info>,
+    #[account(mut, seeds = [b"protocol"], bump = protocol.bump)]
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        close = fee_manager,
+        has_one = protocol,
+        seeds = [b"pending_config", protocol.key().as_ref()],
+        bump = pending_config.bump
+    )]
+    pub pending_config: Account<'info, PendingProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetProtocolRoles<'info> {
     #[account(address = protocol.admin)]
     pub admin: Signer<'info>,
     #[account(mut, seeds = [b"protocol"], bump = protocol.bump)]
@@ -30,13 +67,46 @@ pub struct UpdateProtocolConfig<'info> {
 }
 
 #[derive(Accounts)]
-pub struct SetProtocolPaused<'info> {
+pub struct SetPredictionDelegate<'info> {
+    #[account(address = protocol.delegate_manager @ ErrorCode::MissingProtocolRole)]
+    pub delegate_manager: Signer<'info>,
+    #[account(mut, seeds = [b"protocol"], bump = protocol.bump)]
+    pub protocol: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
     #[account(address = protocol.admin)]
     pub admin: Signer<'info>,
     #[account(mut, seeds = [b"protocol"], bump = protocol.bump)]
     pub protocol: Account<'info, ProtocolConfig>,
 }
 
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(address = protocol.pending_admin @ ErrorCode::NotPendingAdmin)]
+    pub new_admin: Signer<'info>,
+    #[account(mut, seeds = [b"protocol"], bump = protocol.bump)]
+    pub protocol: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetProtocolPauseFlags<'info> {
+    #[account(address = protocol.pauser @ ErrorCode::MissingProtocolRole)]
+    pub pauser: Signer<'info>,
+    #[account(mut, seeds = [b"protocol"], bump = protocol.bump)]
+    pub protocol: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetRoomPauseFlags<'info> {
+    #[account(address = protocol.pauser @ ErrorCode::MissingProtocolRole)]
+    pub pauser: Signer<'info>,
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+}
+
 #[derive(Accounts)]
 pub struct CreateRoom<'info> {
     #[account(mut)]
@@ -53,7 +123,7 @@ pub struct CreateRoom<'info> {
         bump
     )]
     pub room: Account<'info, Room>,
-    /// CHECK: PDA authority for room quote vaults.
+    /// CHECK: PDA authority for the room escrow and bond vault.
     #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
     pub vault_authority: UncheckedAccount<'info>,
     #[account(
@@ -62,31 +132,136 @@ pub struct CreateRoom<'info> {
         associated_token::mint = quote_mint,
         associated_token::authority = vault_authority,
     )]
-    pub artist_pending_vault: Account<'info, TokenAccount>,
+    pub room_escrow: Account<'info, TokenAccount>,
     #[account(
         init,
         payer = artist,
-        associated_token::mint = quote_mint,
-        associated_token::authority = vault_authority,
+        seeds = [b"artist_bond_vault", room.key().as_ref()],
+        bump,
+        token::mint = quote_mint,
+        token::authority = vault_authority,
     )]
-    pub platform_fee_vault: Account<'info, TokenAccount>,
+    pub artist_bond_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRoomFeeOverride<'info> {
+    #[account(mut, address = protocol.fee_manager @ ErrorCode::MissingProtocolRole)]
+    pub fee_manager: Signer<'info>,
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
     #[account(
         init,
-        payer = artist,
-        associated_token::mint = quote_mint,
-        associated_token::authority = vault_authority,
+        payer = fee_manager,
+        space = RoomFeeOverride::LEN,
+        seeds = [b"room_fee_override", room.key().as_ref()],
+        bump
+    )]
+    pub room_fee_override: Account<'info, RoomFeeOverride>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveRoomFeeOverride<'info> {
+    #[account(mut, address = protocol.fee_manager @ ErrorCode::MissingProtocolRole)]
+    pub fee_manager: Signer<'info>,
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(
+        mut,
+        close = fee_manager,
+        has_one = room,
+        seeds = [b"room_fee_override", room.key().as_ref()],
+        bump = room_fee_override.bump
     )]
-    pub liquidity_reserve_vault: Account<'info, TokenAccount>,
+    pub room_fee_override: Account<'info, RoomFeeOverride>,
+}
+
+#[derive(Accounts)]
+pub struct ManageRoomOperators<'info> {
+    #[account(address = room.artist)]
+    pub artist: Signer<'info>,
+    #[account(mut)]
+    pub room: Account<'info, Room>,
+}
+
+#[derive(Accounts)]
+pub struct SetRoomMarketMode<'info> {
+    #[account(address = room.artist)]
+    pub artist: Signer<'info>,
+    #[account(mut)]
+    pub room: Account<'info, Room>,
+}
+
+#[derive(Accounts)]
+pub struct DepositArtistBond<'info> {
+    #[account(address = room.artist)]
+    pub artist: Signer<'info>,
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(address = protocol.quote_mint)]
+    pub quote_mint: Account<'info, Mint>,
+    /// CHECK: PDA authority for the room escrow and bond vault.
+    #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
     #[account(
-        init,
-        payer = artist,
-        associated_token::mint = quote_mint,
-        associated_token::authority = vault_authority,
+        mut,
+        seeds = [b"artist_bond_vault", room.key().as_ref()],
+        bump,
+        token::mint = quote_mint,
+        token::authority = vault_authority,
     )]
-    pub winner_pot_vault: Account<'info, TokenAccount>,
+    pub artist_bond_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = artist_quote_ata.owner == artist.key() @ ErrorCode::InvalidUserQuoteAccount,
+        constraint = artist_quote_ata.mint == quote_mint.key() @ ErrorCode::InvalidUserQuoteAccount,
+    )]
+    pub artist_quote_ata: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RequestArtistBondWithdrawal<'info> {
+    #[account(address = room.artist)]
+    pub artist: Signer<'info>,
+    #[account(mut)]
+    pub room: Account<'info, Room>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawArtistBond<'info> {
+    #[account(address = room.artist)]
+    pub artist: Signer<'info>,
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(address = protocol.quote_mint)]
+    pub quote_mint: Account<'info, Mint>,
+    /// CHECK: PDA authority for the room escrow and bond vault.
+    #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"artist_bond_vault", room.key().as_ref()],
+        bump,
+        token::mint = quote_mint,
+        token::authority = vault_authority,
+    )]
+    pub artist_bond_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = artist_quote_ata.owner == artist.key() @ ErrorCode::InvalidUserQuoteAccount,
+        constraint = artist_quote_ata.mint == quote_mint.key() @ ErrorCode::InvalidUserQuoteAccount,
+    )]
+    pub artist_quote_ata: Account<'info, TokenAccount>,
     pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -104,7 +279,13 @@ pub struct StartRound<'info> {
         seeds = [b"round", room.key().as_ref(), &room.next_round_index.to_le_bytes()],
         bump
     )]
-    pub round: Account<'info, Round>,
+    pub round: Box<Account<'info, Round>>,
+    /// Required when `room.has_fee_override` is set.
+    #[account(
+        seeds = [b"room_fee_override", room.key().as_ref()],
+        bump = room_fee_override.bump
+    )]
+    pub room_fee_override: Option<Account<'info, RoomFeeOverride>>,
     pub system_program: Program<'info, System>,
 }
 
@@ -112,23 +293,23 @@ pub struct StartRound<'info> {
 pub struct CommitRound<'info> {
     #[account(address = room.artist)]
     pub artist: Signer<'info>,
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
     pub room: Account<'info, Room>,
     #[account(mut, has_one = room)]
-    pub round: Account<'info, Round>,
+    pub round: Box<Account<'info, Round>>,
 }
 
 #[derive(Accounts)]
+#[instruction(params: PlacePredictionParams)]
 pub struct PlacePrediction<'info> {
     pub protocol: Account<'info, ProtocolConfig>,
     #[account(constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
     pub room: Account<'info, Room>,
-    #[account(mut, has_one = room)]
-    pub round: Account<'info, Round>,
+    #[account(has_one = room)]
+    pub round: Box<Account<'info, Round>>,
     #[account(address = protocol.quote_mint)]
     pub quote_mint: Account<'info, Mint>,
-    /// CHECK: PDA authority for room quote vaults.
-    #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
-    pub vault_authority: UncheckedAccount<'info>,
     #[account(mut)]
     pub user: Signer<'info>,
     #[account(
@@ -139,37 +320,72 @@ pub struct PlacePrediction<'info> {
     pub user_quote_ata: Account<'info, TokenAccount>,
     #[account(
         mut,
-        associated_token::mint = quote_mint,
-        associated_token::authority = vault_authority,
+        seeds = [b"round_shard", round.key().as_ref(), &[round_shard.index]],
+        bump = round_shard.bump,
     )]
-    pub artist_pending_vault: Account<'info, TokenAccount>,
+    pub round_shard: Box<Account<'info, RoundShard>>,
     #[account(
         mut,
-        associated_token::mint = quote_mint,
-        associated_token::authority = vault_authority,
+        seeds = [b"round_shard_escrow", round_shard.key().as_ref()],
+        bump = round_shard.escrow_bump,
     )]
-    pub platform_fee_vault: Account<'info, TokenAccount>,
+    pub shard_escrow: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = PredictionPosition::space(1),
+        seeds = [
+            b"position",
+            round.key().as_ref(),
+            user.key().as_ref(),
+            &params.client_nonce.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub position: Account<'info, PredictionPosition>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: PlacePredictionBatchParams)]
+pub struct PlacePredictionBatch<'info> {
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(has_one = room)]
+    pub round: Box<Account<'info, Round>>,
+    #[account(address = protocol.quote_mint)]
+    pub quote_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub user: Signer<'info>,
     #[account(
         mut,
-        associated_token::mint = quote_mint,
-        associated_token::authority = vault_authority,
+        constraint = user_quote_ata.owner == user.key() @ ErrorCode::InvalidUserQuoteAccount,
+        constraint = user_quote_ata.mint == quote_mint.key() @ ErrorCode::InvalidUserQuoteAccount,
     )]
-    pub liquidity_reserve_vault: Account<'info, TokenAccount>,
+    pub user_quote_ata: Account<'info, TokenAccount>,
     #[account(
         mut,
-        associated_token::mint = quote_mint,
-        associated_token::authority = vault_authority,
+        seeds = [b"round_shard", round.key().as_ref(), &[round_shard.index]],
+        bump = round_shard.bump,
     )]
-    pub winner_pot_vault: Account<'info, TokenAccount>,
+    pub round_shard: Box<Account<'info, RoundShard>>,
     #[account(
-        init,
+        mut,
+        seeds = [b"round_shard_escrow", round_shard.key().as_ref()],
+        bump = round_shard.escrow_bump,
+    )]
+    pub shard_escrow: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
         payer = user,
-        space = PredictionPosition::LEN,
+        space = PredictionPosition::space(params.entries.len()),
         seeds = [
             b"position",
             round.key().as_ref(),
             user.key().as_ref(),
-            &round.total_predictions.to_le_bytes(),
+            &params.client_nonce.to_le_bytes(),
         ],
         bump
     )]
@@ -179,17 +395,15 @@ pub struct PlacePrediction<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(params: PlacePredictionParams)]
 pub struct PlacePredictionDelegated<'info> {
     pub protocol: Account<'info, ProtocolConfig>,
     #[account(constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
     pub room: Account<'info, Room>,
     #[account(mut, has_one = room)]
-    pub round: Account<'info, Round>,
+    pub round: Box<Account<'info, Round>>,
     #[account(address = protocol.quote_mint)]
     pub quote_mint: Account<'info, Mint>,
-    /// CHECK: PDA authority for room quote vaults.
-    #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
-    pub vault_authority: UncheckedAccount<'info>,
     #[account(mut, address = protocol.prediction_delegate @ ErrorCode::InvalidDelegatedPredictionSigner)]
     pub session_delegate: Signer<'info>,
     /// CHECK: Owner checked against user_quote_ata.owner.
@@ -202,37 +416,69 @@ pub struct PlacePredictionDelegated<'info> {
     pub user_quote_ata: Account<'info, TokenAccount>,
     #[account(
         mut,
-        associated_token::mint = quote_mint,
-        associated_token::authority = vault_authority,
+        seeds = [b"round_shard", round.key().as_ref(), &[round_shard.index]],
+        bump = round_shard.bump,
     )]
-    pub artist_pending_vault: Account<'info, TokenAccount>,
+    pub round_shard: Box<Account<'info, RoundShard>>,
     #[account(
         mut,
-        associated_token::mint = quote_mint,
-        associated_token::authority = vault_authority,
+        seeds = [b"round_shard_escrow", round_shard.key().as_ref()],
+        bump = round_shard.escrow_bump,
     )]
-    pub platform_fee_vault: Account<'info, TokenAccount>,
+    pub shard_escrow: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = session_delegate,
+        space = PredictionPosition::space(1),
+        seeds = [
+            b"position",
+            round.key().as_ref(),
+            user.key().as_ref(),
+            &params.client_nonce.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub position: Account<'info, PredictionPosition>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: BuyTileSharesParams)]
+pub struct BuyTileShares<'info> {
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(mut, has_one = room)]
+    pub round: Box<Account<'info, Round>>,
+    #[account(address = protocol.quote_mint)]
+    pub quote_mint: Account<'info, Mint>,
+    /// CHECK: PDA authority for the room escrow and bond vault.
+    #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
     #[account(
         mut,
-        associated_token::mint = quote_mint,
-        associated_token::authority = vault_authority,
+        constraint = user_quote_ata.owner == user.key() @ ErrorCode::InvalidUserQuoteAccount,
+        constraint = user_quote_ata.mint == quote_mint.key() @ ErrorCode::InvalidUserQuoteAccount,
     )]
-    pub liquidity_reserve_vault: Account<'info, TokenAccount>,
+    pub user_quote_ata: Account<'info, TokenAccount>,
     #[account(
         mut,
         associated_token::mint = quote_mint,
         associated_token::authority = vault_authority,
     )]
-    pub winner_pot_vault: Account<'info, TokenAccount>,
+    pub room_escrow: Account<'info, TokenAccount>,
     #[account(
-        init,
-        payer = session_delegate,
-        space = PredictionPosition::LEN,
+        init_if_needed,
+        payer = user,
+        space = PredictionPosition::space(1),
         seeds = [
             b"position",
             round.key().as_ref(),
             user.key().as_ref(),
-            &round.total_predictions.to_le_bytes(),
+            &params.client_nonce.to_le_bytes(),
         ],
         bump
     )]
@@ -242,28 +488,113 @@ pub struct PlacePredictionDelegated<'info> {
 }
 
 #[derive(Accounts)]
-pub struct MutateRound<'info> {
-    #[account(address = room.artist)]
-    pub artist: Signer<'info>,
+pub struct CancelPrediction<'info> {
+    #[account(mut, address = position.user)]
+    pub user: Signer<'info>,
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(mut, has_one = room)]
+    pub round: Box<Account<'info, Round>>,
+    /// The user's shard, folded before the position is reversed. Required
+    /// for parimutuel rounds; LMSR rounds have no shards.
+    #[account(
+        mut,
+        seeds = [b"round_shard", round.key().as_ref(), &[round_shard.index]],
+        bump = round_shard.bump,
+    )]
+    pub round_shard: Option<Box<Account<'info, RoundShard>>>,
+    #[account(
+        mut,
+        seeds = [
+            b"round_shard_escrow",
+            round_shard.as_ref().map(|shard| shard.key()).unwrap_or_default().as_ref(),
+        ],
+        bump = round_shard.as_ref().map_or(0, |shard| shard.escrow_bump),
+    )]
+    pub shard_escrow: Option<Account<'info, TokenAccount>>,
+    /// Not closed: it is kept as a tombstone for its nonce until the round
+    /// is over.
+    #[account(
+        mut,
+        has_one = round,
+        has_one = user,
+        constraint = !position.settled @ ErrorCode::PositionAlreadySettled,
+    )]
+    pub position: Account<'info, PredictionPosition>,
+    #[account(address = protocol.quote_mint)]
+    pub quote_mint: Account<'info, Mint>,
+    /// CHECK: PDA authority for the room escrow and bond vault.
+    #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = quote_mint,
+        associated_token::authority = vault_authority,
+    )]
+    pub room_escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_quote_ata.owner == user.key() @ ErrorCode::InvalidUserQuoteAccount,
+        constraint = user_quote_ata.mint == quote_mint.key() @ ErrorCode::InvalidUserQuoteAccount,
+    )]
+    pub user_quote_ata: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct LockRound<'info> {
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
     pub room: Account<'info, Room>,
     #[account(mut, has_one = room)]
-    pub round: Account<'info, Round>,
+    pub round: Box<Account<'info, Round>>,
+    #[account(address = protocol.quote_mint)]
+    pub quote_mint: Account<'info, Mint>,
+    /// CHECK: PDA authority for the room escrow and bond vault.
+    #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = quote_mint,
+        associated_token::authority = vault_authority,
+    )]
+    pub room_escrow: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct SettlePosition<'info> {
+pub struct MutateRound<'info> {
     #[account(address = room.artist)]
     pub artist: Signer<'info>,
     pub protocol: Account<'info, ProtocolConfig>,
     #[account(constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
     pub room: Account<'info, Room>,
     #[account(mut, has_one = room)]
-    pub round: Account<'info, Round>,
+    pub round: Box<Account<'info, Round>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRound<'info> {
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(mut, has_one = room)]
+    pub round: Box<Account<'info, Round>>,
+}
+
+#[derive(Accounts)]
+pub struct RefundPosition<'info> {
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(mut, has_one = room)]
+    pub round: Box<Account<'info, Round>>,
     #[account(mut, has_one = round)]
     pub position: Account<'info, PredictionPosition>,
     #[account(address = protocol.quote_mint)]
     pub quote_mint: Account<'info, Mint>,
-    /// CHECK: PDA authority for room quote vaults.
+    /// CHECK: PDA authority for the room escrow and bond vault.
     #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
     pub vault_authority: UncheckedAccount<'info>,
     #[account(
@@ -271,7 +602,15 @@ pub struct SettlePosition<'info> {
         associated_token::mint = quote_mint,
         associated_token::authority = vault_authority,
     )]
-    pub winner_pot_vault: Account<'info, TokenAccount>,
+    pub room_escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"artist_bond_vault", room.key().as_ref()],
+        bump,
+        token::mint = quote_mint,
+        token::authority = vault_authority,
+    )]
+    pub artist_bond_vault: Account<'info, TokenAccount>,
     #[account(
         mut,
         constraint = user_quote_ata.owner == position.user @ ErrorCode::InvalidUserQuoteAccount,
@@ -282,17 +621,52 @@ pub struct SettlePosition<'info> {
 }
 
 #[derive(Accounts)]
-pub struct SettleRound<'info> {
+pub struct TallyPosition<'info> {
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(mut, has_one = room)]
+    pub round: Box<Account<'info, Round>>,
+    #[account(mut, has_one = round)]
+    pub position: Account<'info, PredictionPosition>,
+}
+
+#[derive(Accounts)]
+pub struct SettlePosition<'info> {
     #[account(address = room.artist)]
     pub artist: Signer<'info>,
     pub protocol: Account<'info, ProtocolConfig>,
-    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    #[account(constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(mut, has_one = room)]
+    pub round: Box<Account<'info, Round>>,
+    #[account(mut, has_one = round)]
+    pub position: Account<'info, PredictionPosition>,
+}
+
+#[derive(Accounts)]
+pub struct SettlePositions<'info> {
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
     pub room: Account<'info, Room>,
     #[account(mut, has_one = room)]
-    pub round: Account<'info, Round>,
+    pub round: Box<Account<'info, Round>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+    #[account(address = position.user)]
+    pub user: Signer<'info>,
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(has_one = room)]
+    pub round: Box<Account<'info, Round>>,
+    #[account(mut, has_one = round, has_one = user)]
+    pub position: Account<'info, PredictionPosition>,
     #[account(address = protocol.quote_mint)]
     pub quote_mint: Account<'info, Mint>,
-    /// CHECK: PDA authority for room quote vaults.
+    /// CHECK: PDA authority for the room escrow and bond vault.
     #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
     pub vault_authority: UncheckedAccount<'info>,
     #[account(
@@ -300,26 +674,37 @@ pub struct SettleRound<'info> {
         associated_token::mint = quote_mint,
         associated_token::authority = vault_authority,
     )]
-    pub artist_pending_vault: Account<'info, TokenAccount>,
+    pub room_escrow: Account<'info, TokenAccount>,
     #[account(
         mut,
-        associated_token::mint = quote_mint,
-        associated_token::authority = vault_authority,
+        constraint = user_quote_ata.owner == user.key() @ ErrorCode::InvalidUserQuoteAccount,
+        constraint = user_quote_ata.mint == quote_mint.key() @ ErrorCode::InvalidUserQuoteAccount,
     )]
-    pub liquidity_reserve_vault: Account<'info, TokenAccount>,
+    pub user_quote_ata: Account<'info, TokenAccount>,
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct SettleRound<'info> {
+    #[account(address = room.artist)]
+    pub artist: Signer<'info>,
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(mut, has_one = room)]
+    pub round: Box<Account<'info, Round>>,
+}
+
 #[derive(Accounts)]
 pub struct ClaimArtistPending<'info> {
     #[account(address = room.artist)]
     pub artist: Signer<'info>,
     pub protocol: Account<'info, ProtocolConfig>,
-    #[account(constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
     pub room: Account<'info, Room>,
     #[account(address = protocol.quote_mint)]
     pub quote_mint: Account<'info, Mint>,
-    /// CHECK: PDA authority for room quote vaults.
+    /// CHECK: PDA authority for the room escrow and bond vault.
     #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
     pub vault_authority: UncheckedAccount<'info>,
     #[account(
@@ -327,7 +712,7 @@ pub struct ClaimArtistPending<'info> {
         associated_token::mint = quote_mint,
         associated_token::authority = vault_authority,
     )]
-    pub artist_pending_vault: Account<'info, TokenAccount>,
+    pub room_escrow: Account<'info, TokenAccount>,
     #[account(
         mut,
         constraint = artist_quote_ata.owner == artist.key() @ ErrorCode::InvalidUserQuoteAccount,
@@ -337,18 +722,91 @@ pub struct ClaimArtistPending<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+/// Read-only: compares the room escrow balance against the liabilities
+/// booked on the room.
 #[derive(Accounts)]
-pub struct ClaimPlatformFee<'info> {
-    #[account(address = protocol.admin)]
-    pub admin: Signer<'info>,
+pub struct ReconcileRoomEscrow<'info> {
     pub protocol: Account<'info, ProtocolConfig>,
     #[account(constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
     pub room: Account<'info, Room>,
     #[account(address = protocol.quote_mint)]
     pub quote_mint: Account<'info, Mint>,
-    /// CHECK: PDA authority for room quote vaults.
+    /// CHECK: PDA authority for the room escrow and bond vault.
+    #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(
+        associated_token::mint = quote_mint,
+        associated_token::authority = vault_authority,
+    )]
+    pub room_escrow: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(mut, address = protocol.admin)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [b"protocol"], bump = protocol.bump)]
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(address = protocol.quote_mint)]
+    pub quote_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        space = ProtocolTreasury::LEN,
+        seeds = [b"treasury", protocol.key().as_ref()],
+        bump
+    )]
+    pub protocol_treasury: Account<'info, ProtocolTreasury>,
+    #[account(
+        init,
+        payer = admin,
+        associated_token::mint = quote_mint,
+        associated_token::authority = protocol_treasury,
+    )]
+    pub treasury_quote_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Permissionless; `remaining_accounts` holds `(room, vault_authority,
+/// room_escrow)` triples.
+#[derive(Accounts)]
+pub struct SweepPlatformFees<'info> {
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(address = protocol.quote_mint)]
+    pub quote_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        has_one = protocol,
+        seeds = [b"treasury", protocol.key().as_ref()],
+        bump = protocol_treasury.bump
+    )]
+    pub protocol_treasury: Account<'info, ProtocolTreasury>,
+    #[account(
+        mut,
+        associated_token::mint = quote_mint,
+        associated_token::authority = protocol_treasury,
+    )]
+    pub treasury_quote_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Single-room alternative to `sweep_platform_fees`. Account names are kept
+/// from before the room escrow so existing clients still build it.
+#[derive(Accounts)]
+pub struct ClaimPlatformFee<'info> {
+    #[account(address = protocol.treasury @ ErrorCode::MissingProtocolRole)]
+    pub treasury: Signer<'info>,
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(address = protocol.quote_mint)]
+    pub quote_mint: Account<'info, Mint>,
+    /// CHECK: PDA authority for the room escrow and bond vault.
     #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
     pub vault_authority: UncheckedAccount<'info>,
+    /// The room escrow, at the same ATA address the platform fee vault had.
     #[account(
         mut,
         associated_token::mint = quote_mint,
@@ -357,13 +815,42 @@ pub struct ClaimPlatformFee<'info> {
     pub platform_fee_vault: Account<'info, TokenAccount>,
     #[account(
         mut,
-        constraint = platform_treasury_quote_ata.owner == admin.key() @ ErrorCode::InvalidUserQuoteAccount,
-        constraint = platform_treasury_quote_ata.mint == quote_mint.key() @ ErrorCode::InvalidUserQuoteAccount,
+        address = protocol.treasury_destination @ ErrorCode::InvalidTreasuryDestination,
+        constraint = platform_treasury_quote_ata.mint == quote_mint.key() @ ErrorCode::InvalidTreasuryDestination,
     )]
     pub platform_treasury_quote_ata: Account<'info, TokenAccount>,
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(address = protocol.treasury @ ErrorCode::MissingProtocolRole)]
+    pub treasury: Signer<'info>,
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(address = protocol.quote_mint)]
+    pub quote_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        has_one = protocol,
+        seeds = [b"treasury", protocol.key().as_ref()],
+        bump = protocol_treasury.bump
+    )]
+    pub protocol_treasury: Account<'info, ProtocolTreasury>,
+    #[account(
+        mut,
+        associated_token::mint = quote_mint,
+        associated_token::authority = protocol_treasury,
+    )]
+    pub treasury_quote_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = protocol.treasury_destination @ ErrorCode::InvalidTreasuryDestination,
+        constraint = treasury_destination.mint == quote_mint.key() @ ErrorCode::InvalidTreasuryDestination,
+    )]
+    pub treasury_destination: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
 #[derive(Accounts)]
 pub struct ClaimRewardToken<'info> {
     #[account(address = position.user)]
@@ -372,7 +859,7 @@ pub struct ClaimRewardToken<'info> {
     #[account(constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
     pub room: Account<'info, Room>,
     #[account(has_one = room)]
-    pub round: Account<'info, Round>,
+    pub round: Box<Account<'info, Round>>,
     #[account(mut, has_one = round, has_one = user)]
     pub position: Account<'info, PredictionPosition>,
     #[account(address = room.reward_mint)]
@@ -389,16 +876,107 @@ pub struct ClaimRewardToken<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeRoundShard<'info> {
+    #[account(mut, address = room.artist)]
+    pub artist: Signer<'info>,
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(mut, has_one = room)]
+    pub round: Box<Account<'info, Round>>,
+    #[account(
+        init,
+        payer = artist,
+        space = RoundShard::LEN,
+        seeds = [b"round_shard", round.key().as_ref(), &[round.shard_count]],
+        bump
+    )]
+    pub round_shard: Box<Account<'info, RoundShard>>,
+    #[account(address = protocol.quote_mint)]
+    pub quote_mint: Account<'info, Mint>,
+    /// CHECK: PDA authority for the room escrow and bond vault.
+    #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = artist,
+        seeds = [b"round_shard_escrow", round_shard.key().as_ref()],
+        bump,
+        token::mint = quote_mint,
+        token::authority = vault_authority,
+    )]
+    pub shard_escrow: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRoundShard<'info> {
+    #[account(mut, address = room.artist)]
+    pub artist: Signer<'info>,
+    pub protocol: Account<'info, ProtocolConfig>,
+    #[account(constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
+    pub room: Account<'info, Room>,
+    #[account(mut, has_one = room)]
+    pub round: Box<Account<'info, Round>>,
+    #[account(
+        mut,
+        close = artist,
+        seeds = [b"round_shard", round.key().as_ref(), &[round_shard.index]],
+        bump = round_shard.bump,
+    )]
+    pub round_shard: Box<Account<'info, RoundShard>>,
+    #[account(address = protocol.quote_mint)]
+    pub quote_mint: Account<'info, Mint>,
+    /// CHECK: PDA authority for the room escrow and bond vault.
+    #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"round_shard_escrow", round_shard.key().as_ref()],
+        bump = round_shard.escrow_bump,
+    )]
+    pub shard_escrow: Account<'info, TokenAccount>,
+    /// Receives anything sent to the shard escrow after its last fold.
+    #[account(
+        mut,
+        associated_token::mint = quote_mint,
+        associated_token::authority = vault_authority,
+    )]
+    pub room_escrow: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    #[account(mut, address = position.user)]
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub round: Box<Account<'info, Round>>,
+    #[account(mut, close = user, has_one = round, has_one = user)]
+    pub position: Account<'info, PredictionPosition>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRound<'info> {
+    #[account(mut, address = room.artist)]
+    pub artist: Signer<'info>,
+    pub room: Account<'info, Room>,
+    #[account(mut, close = artist, has_one = room)]
+    pub round: Box<Account<'info, Round>>,
+}
+
 #[derive(Accounts)]
 pub struct DeployLiquidityReserve<'info> {
-    #[account(address = protocol.admin)]
-    pub admin: Signer<'info>,
+    #[account(address = protocol.liquidity_deployer @ ErrorCode::MissingProtocolRole)]
+    pub liquidity_deployer: Signer<'info>,
     pub protocol: Account<'info, ProtocolConfig>,
     #[account(mut, constraint = room.protocol == protocol.key() @ ErrorCode::InvalidRoomProtocol)]
     pub room: Account<'info, Room>,
     #[account(address = protocol.quote_mint)]
     pub quote_mint: Account<'info, Mint>,
-    /// CHECK: PDA authority for room quote vaults.
+    /// CHECK: PDA authority for the room escrow and bond vault.
     #[account(seeds = [b"vault_authority", room.key().as_ref()], bump)]
     pub vault_authority: UncheckedAccount<'info>,
     #[account(
@@ -406,7 +984,7 @@ pub struct DeployLiquidityReserve<'info> {
         associated_token::mint = quote_mint,
         associated_token::authority = vault_authority,
     )]
-    pub liquidity_reserve_vault: Account<'info, TokenAccount>,
+    pub room_escrow: Account<'info, TokenAccount>,
     #[account(
         mut,
         constraint = destination_quote_ata.mint == quote_mint.key() @ ErrorCode::InvalidUserQuoteAccount,
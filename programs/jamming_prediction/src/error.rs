@@ -48,4 +48,94 @@ pub enum ErrorCode {
     RewardMintFreezeAuthorityMismatch,
     #[msg("Insufficient pending liquidity reserve for deployment")]
     InsufficientPendingLiquidityReserve,
+    #[msg("Position already tallied")]
+    PositionAlreadyTallied,
+    #[msg("All positions must be tallied before settlement")]
+    UntalliedPositions,
+    #[msg("Reveal timeout must be positive")]
+    InvalidRevealTimeout,
+    #[msg("Reveal deadline has not passed")]
+    RevealDeadlineNotReached,
+    #[msg("Prediction close time must be in the future")]
+    InvalidPredictionCloseTime,
+    #[msg("Prediction window has closed")]
+    PredictionWindowClosed,
+    #[msg("Prediction window is still open")]
+    PredictionWindowStillOpen,
+    #[msg("Unsupported reveal commitment version")]
+    UnsupportedCommitVersion,
+    #[msg("Artist bond configuration is invalid")]
+    InvalidArtistBondConfig,
+    #[msg("Artist bond is below the protocol minimum")]
+    InsufficientArtistBond,
+    #[msg("Artist bond withdrawal exceeds the bonded amount")]
+    InvalidBondWithdrawalAmount,
+    #[msg("No artist bond withdrawal has been requested")]
+    NoBondWithdrawalRequested,
+    #[msg("Artist bond withdrawal cooldown has not elapsed")]
+    BondWithdrawalCooldownActive,
+    #[msg("Artist bond is locked by an unsettled round")]
+    ArtistBondLocked,
+    #[msg("Room artist and operators cannot predict in their own room")]
+    ExcludedPredictor,
+    #[msg("Room operator list is full")]
+    RoomOperatorListFull,
+    #[msg("Wallet is already excluded from this room")]
+    RoomOperatorAlreadyRegistered,
+    #[msg("Room operator not found")]
+    RoomOperatorNotFound,
+    #[msg("Instruction is not supported in this round's market mode")]
+    InvalidMarketMode,
+    #[msg("LMSR liquidity parameter must be positive")]
+    InvalidLmsrLiquidity,
+    #[msg("Liquidity reserve cannot fund the LMSR subsidy")]
+    InsufficientLmsrLiquidity,
+    #[msg("Purchase yields fewer shares than the requested minimum")]
+    SharesBelowMinimum,
+    #[msg("Cancellation fee must not exceed 10000 bps")]
+    InvalidCancellationFee,
+    #[msg("Batch must contain between 1 and MAX_BATCH_ENTRIES entries")]
+    InvalidBatchSize,
+    #[msg("Remaining accounts do not match the layout this instruction expects")]
+    InvalidRemainingAccounts,
+    #[msg("Position has no USDC winnings to claim")]
+    NoWinningsToClaim,
+    #[msg("Position USDC winnings already claimed")]
+    WinningsAlreadyClaimed,
+    #[msg("Position still has unclaimed winnings or reward tokens")]
+    PositionHasUnclaimedBalance,
+    #[msg("Round still has open position accounts")]
+    OpenPositionsRemain,
+    #[msg("Config timelock must not be negative")]
+    InvalidConfigTimelock,
+    #[msg("Pending config change is still timelocked")]
+    ConfigTimelockActive,
+    #[msg("Signer is not the pending admin")]
+    NotPendingAdmin,
+    #[msg("Signer does not hold the protocol role required for this instruction")]
+    MissingProtocolRole,
+    #[msg("Room is paused")]
+    RoomPaused,
+    #[msg("Unknown pause flag bits")]
+    InvalidPauseFlags,
+    #[msg("Room fee override account must be passed exactly when the room has one")]
+    FeeOverrideMismatch,
+    #[msg("Treasury destination must be a quote token account")]
+    InvalidTreasuryDestination,
+    #[msg("Artist claim exceeds earned minus already claimed")]
+    ArtistClaimExceedsEarned,
+    #[msg("Room vault balance is below its booked liabilities")]
+    EscrowLedgerShortfall,
+    #[msg("Round has no prediction shards")]
+    RoundShardsMissing,
+    #[msg("Round already has MAX_ROUND_SHARDS shards")]
+    MaxRoundShardsReached,
+    #[msg("Shard is not the one this user's predictions map to")]
+    WrongRoundShard,
+    #[msg("Round still has open shard accounts")]
+    RoundShardsRemain,
+    #[msg("A position with this client nonce already exists for this user and round")]
+    DuplicatePositionNonce,
+    #[msg("Platform fee claim exceeds the room's accrued platform fees")]
+    PlatformFeeClaimExceedsAccrued,
 }
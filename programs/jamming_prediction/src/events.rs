@@ -6,7 +6,45 @@ pub struct PredictionPlaced {
     pub round: Pubkey,
     pub user: Pubkey,
     pub stake_amount_usdc_minor: u64,
+    pub entry_count: u8,
     pub delegated: bool,
+    /// The placement's `client_nonce`, for matching it to the client request.
+    pub client_nonce: u64,
+    pub platform_fee_bps: u16,
+    pub artist_pending_bps: u16,
+    pub liquidity_reserve_bps: u16,
+    pub winner_pot_bps: u16,
+}
+
+#[event]
+pub struct TileSharesBought {
+    pub room: Pubkey,
+    pub round: Pubkey,
+    pub user: Pubkey,
+    pub track_index: u8,
+    pub step_index: u8,
+    pub will_be_active: bool,
+    pub cost_usdc_minor: u64,
+    pub shares: u64,
+}
+
+/// Only in the logs of the failed `ExcludedPredictor` transaction (see README).
+#[event]
+pub struct PredictionBlocked {
+    pub room: Pubkey,
+    pub round: Pubkey,
+    pub user: Pubkey,
+    pub delegated: bool,
+}
+
+#[event]
+pub struct PredictionCancelled {
+    pub room: Pubkey,
+    pub round: Pubkey,
+    pub position: Pubkey,
+    pub user: Pubkey,
+    pub refund_usdc_minor: u64,
+    pub fee_usdc_minor: u64,
 }
 
 #[event]
@@ -42,3 +80,175 @@ pub struct LiquidityReserveDeployed {
     pub amount_usdc_minor: u64,
     pub destination_quote_ata: Pubkey,
 }
+
+#[event]
+pub struct RoundCancelled {
+    pub room: Pubkey,
+    pub round: Pubkey,
+    pub reveal_deadline_ts: i64,
+    pub total_staked_usdc_minor: u64,
+}
+
+#[event]
+pub struct WinningsClaimed {
+    pub room: Pubkey,
+    pub round: Pubkey,
+    pub position: Pubkey,
+    pub user: Pubkey,
+    pub amount_usdc_minor: u64,
+}
+
+#[event]
+pub struct PositionRefunded {
+    pub room: Pubkey,
+    pub round: Pubkey,
+    pub position: Pubkey,
+    pub user: Pubkey,
+    pub refund_usdc_minor: u64,
+}
+
+#[event]
+pub struct ArtistBondDeposited {
+    pub room: Pubkey,
+    pub amount_usdc_minor: u64,
+    pub bond_usdc_minor: u64,
+}
+
+#[event]
+pub struct ArtistBondWithdrawn {
+    pub room: Pubkey,
+    pub amount_usdc_minor: u64,
+    pub bond_usdc_minor: u64,
+}
+
+#[event]
+pub struct ArtistBondSlashed {
+    pub room: Pubkey,
+    pub round: Pubkey,
+    pub slash_usdc_minor: u64,
+    pub bond_usdc_minor: u64,
+}
+
+#[event]
+pub struct ProtocolConfigProposed {
+    pub protocol: Pubkey,
+    pub pending_config: Pubkey,
+    pub proposed_ts: i64,
+    pub executable_ts: i64,
+}
+
+#[event]
+pub struct ProtocolConfigExecuted {
+    pub protocol: Pubkey,
+    pub pending_config: Pubkey,
+}
+
+#[event]
+pub struct ProtocolConfigCancelled {
+    pub protocol: Pubkey,
+    pub pending_config: Pubkey,
+}
+
+#[event]
+pub struct AdminProposed {
+    pub protocol: Pubkey,
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+}
+
+#[event]
+pub struct AdminTransferred {
+    pub protocol: Pubkey,
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+#[event]
+pub struct ProtocolRolesUpdated {
+    pub protocol: Pubkey,
+    pub pauser: Pubkey,
+    pub fee_manager: Pubkey,
+    pub treasury: Pubkey,
+    pub liquidity_deployer: Pubkey,
+    pub delegate_manager: Pubkey,
+}
+
+#[event]
+pub struct PredictionDelegateUpdated {
+    pub protocol: Pubkey,
+    pub prediction_delegate: Pubkey,
+    pub delegate_max_stake_usdc_minor: u64,
+}
+
+#[event]
+pub struct ProtocolPauseFlagsUpdated {
+    pub protocol: Pubkey,
+    pub pause_flags: u8,
+}
+
+#[event]
+pub struct RoomPauseFlagsUpdated {
+    pub room: Pubkey,
+    pub pause_flags: u8,
+}
+
+#[event]
+pub struct RoomFeeOverrideSet {
+    pub room: Pubkey,
+    pub platform_fee_bps: u16,
+    pub artist_pending_bps: u16,
+    pub liquidity_reserve_bps: u16,
+    pub winner_pot_bps: u16,
+}
+
+#[event]
+pub struct RoomFeeOverrideRemoved {
+    pub room: Pubkey,
+}
+
+#[event]
+pub struct PlatformFeesSwept {
+    pub room: Pubkey,
+    pub amount_usdc_minor: u64,
+}
+
+/// A room `sweep_platform_fees` left alone; neither flag set means nothing accrued.
+#[event]
+pub struct PlatformFeeSweepSkipped {
+    pub room: Pubkey,
+    pub claims_paused: bool,
+    pub insolvent: bool,
+}
+
+#[event]
+pub struct PlatformFeeClaimed {
+    pub room: Pubkey,
+    pub destination: Pubkey,
+    pub amount_usdc_minor: u64,
+    pub platform_fee_accrued_usdc_minor: u64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub destination: Pubkey,
+    pub amount_usdc_minor: u64,
+}
+
+#[event]
+pub struct ArtistPendingClaimed {
+    pub room: Pubkey,
+    pub artist: Pubkey,
+    pub amount_usdc_minor: u64,
+    pub artist_earned_usdc_minor: u64,
+    pub artist_claimed_usdc_minor: u64,
+}
+
+#[event]
+pub struct RoomEscrowReconciled {
+    pub room: Pubkey,
+    pub escrow_balance_usdc_minor: u64,
+    pub liabilities_usdc_minor: u64,
+    pub shortfall_usdc_minor: u64,
+    pub surplus_usdc_minor: u64,
+    pub solvent: bool,
+}
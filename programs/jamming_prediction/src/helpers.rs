@@ -1,11 +1,17 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::{hash::hashv, program_option::COption};
-use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount, TransferChecked};
+use anchor_spl::token::{self, CloseAccount, Mint, MintTo, Token, TokenAccount, TransferChecked};
 
 use crate::{
-    constants::{MAX_STEPS, MAX_TRACKS, REVEAL_BITMAP_BYTES},
+    constants::{
+        MAX_BATCH_ENTRIES, MAX_STEPS, MAX_TRACKS, PAUSE_ALL, REVEAL_BITMAP_BYTES,
+        REVEAL_COMMIT_VERSION_V1, REVEAL_COMMIT_VERSION_V2,
+    },
     error::ErrorCode,
-    state::Room,
+    state::{
+        DustDestination, MarketMode, PredictionPosition, ProtocolConfig, ProtocolTreasury, Room,
+        Round,
+    },
 };
 
 pub fn validate_fee_split(
@@ -32,6 +38,230 @@ pub fn split_amount(total: u64, bps: u16) -> Result<u64> {
         .ok_or(ErrorCode::MathOverflow.into())
 }
 
+/// Escrow sub-ledger legs of a single stake.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StakeSplit {
+    pub artist_pending_usdc_minor: u64,
+    pub platform_fee_usdc_minor: u64,
+    pub liquidity_reserve_usdc_minor: u64,
+    pub winner_pot_usdc_minor: u64,
+}
+
+/// Splits a stake into its four legs. Each leg gets the floor of its bps
+/// share and the rounding dust left over (at most three minor units) goes to
+/// `dust_destination`, so the legs always sum to the stake. Returns the split
+/// and the dust.
+pub fn split_stake(
+    stake_usdc_minor: u64,
+    artist_pending_bps: u16,
+    platform_fee_bps: u16,
+    liquidity_reserve_bps: u16,
+    winner_pot_bps: u16,
+    dust_destination: DustDestination,
+) -> Result<(StakeSplit, u64)> {
+    let mut split = StakeSplit {
+        artist_pending_usdc_minor: split_amount(stake_usdc_minor, artist_pending_bps)?,
+        platform_fee_usdc_minor: split_amount(stake_usdc_minor, platform_fee_bps)?,
+        liquidity_reserve_usdc_minor: split_amount(stake_usdc_minor, liquidity_reserve_bps)?,
+        winner_pot_usdc_minor: split_amount(stake_usdc_minor, winner_pot_bps)?,
+    };
+    let dust = stake_usdc_minor
+        .checked_sub(stake_split_total(&split)?)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let dust_leg = match dust_destination {
+        DustDestination::WinnerPot => &mut split.winner_pot_usdc_minor,
+        DustDestination::ArtistPending => &mut split.artist_pending_usdc_minor,
+        DustDestination::PlatformFee => &mut split.platform_fee_usdc_minor,
+        DustDestination::LiquidityReserve => &mut split.liquidity_reserve_usdc_minor,
+    };
+    *dust_leg = dust_leg.checked_add(dust).ok_or(ErrorCode::MathOverflow)?;
+
+    Ok((split, dust))
+}
+
+/// Leg-wise sum of two splits, used to aggregate batch entries into one
+/// escrow transfer.
+pub fn add_stake_splits(a: &StakeSplit, b: &StakeSplit) -> Result<StakeSplit> {
+    Ok(StakeSplit {
+        artist_pending_usdc_minor: a
+            .artist_pending_usdc_minor
+            .checked_add(b.artist_pending_usdc_minor)
+            .ok_or(ErrorCode::MathOverflow)?,
+        platform_fee_usdc_minor: a
+            .platform_fee_usdc_minor
+            .checked_add(b.platform_fee_usdc_minor)
+            .ok_or(ErrorCode::MathOverflow)?,
+        liquidity_reserve_usdc_minor: a
+            .liquidity_reserve_usdc_minor
+            .checked_add(b.liquidity_reserve_usdc_minor)
+            .ok_or(ErrorCode::MathOverflow)?,
+        winner_pot_usdc_minor: a
+            .winner_pot_usdc_minor
+            .checked_add(b.winner_pot_usdc_minor)
+            .ok_or(ErrorCode::MathOverflow)?,
+    })
+}
+
+/// Leg-wise difference of two splits.
+pub fn sub_stake_splits(a: &StakeSplit, b: &StakeSplit) -> Result<StakeSplit> {
+    Ok(StakeSplit {
+        artist_pending_usdc_minor: a
+            .artist_pending_usdc_minor
+            .checked_sub(b.artist_pending_usdc_minor)
+            .ok_or(ErrorCode::MathOverflow)?,
+        platform_fee_usdc_minor: a
+            .platform_fee_usdc_minor
+            .checked_sub(b.platform_fee_usdc_minor)
+            .ok_or(ErrorCode::MathOverflow)?,
+        liquidity_reserve_usdc_minor: a
+            .liquidity_reserve_usdc_minor
+            .checked_sub(b.liquidity_reserve_usdc_minor)
+            .ok_or(ErrorCode::MathOverflow)?,
+        winner_pot_usdc_minor: a
+            .winner_pot_usdc_minor
+            .checked_sub(b.winner_pot_usdc_minor)
+            .ok_or(ErrorCode::MathOverflow)?,
+    })
+}
+
+/// Liability legs currently booked against the room escrow.
+pub fn room_escrow_liabilities(room: &Room) -> StakeSplit {
+    StakeSplit {
+        artist_pending_usdc_minor: room.artist_pending_liability_usdc_minor,
+        platform_fee_usdc_minor: room.platform_fee_liability_usdc_minor,
+        liquidity_reserve_usdc_minor: room.liquidity_reserve_liability_usdc_minor,
+        winner_pot_usdc_minor: room.winner_pot_liability_usdc_minor,
+    }
+}
+
+fn set_room_escrow_liabilities(room: &mut Room, legs: &StakeSplit) {
+    room.artist_pending_liability_usdc_minor = legs.artist_pending_usdc_minor;
+    room.platform_fee_liability_usdc_minor = legs.platform_fee_usdc_minor;
+    room.liquidity_reserve_liability_usdc_minor = legs.liquidity_reserve_usdc_minor;
+    room.winner_pot_liability_usdc_minor = legs.winner_pot_usdc_minor;
+}
+
+/// Books quote that entered the room escrow against the matching legs.
+pub fn credit_room_escrow_ledger(room: &mut Room, legs: &StakeSplit) -> Result<()> {
+    let liabilities = add_stake_splits(&room_escrow_liabilities(room), legs)?;
+    set_room_escrow_liabilities(room, &liabilities);
+    Ok(())
+}
+
+/// Releases quote that left the room escrow from the matching legs.
+pub fn debit_room_escrow_ledger(room: &mut Room, legs: &StakeSplit) -> Result<()> {
+    let liabilities = sub_stake_splits(&room_escrow_liabilities(room), legs)?;
+    set_room_escrow_liabilities(room, &liabilities);
+    Ok(())
+}
+
+/// Cancellation fees a round has kept from withdrawn positions, per leg.
+pub fn round_cancellation_fees(round: &Round) -> StakeSplit {
+    StakeSplit {
+        artist_pending_usdc_minor: round.cancellation_fee_artist_pending_usdc_minor,
+        platform_fee_usdc_minor: round.cancellation_fee_platform_usdc_minor,
+        liquidity_reserve_usdc_minor: round.cancellation_fee_liquidity_reserve_usdc_minor,
+        winner_pot_usdc_minor: round.cancellation_fee_winner_pot_usdc_minor,
+    }
+}
+
+pub fn set_round_cancellation_fees(round: &mut Round, fees: &StakeSplit) {
+    round.cancellation_fee_artist_pending_usdc_minor = fees.artist_pending_usdc_minor;
+    round.cancellation_fee_platform_usdc_minor = fees.platform_fee_usdc_minor;
+    round.cancellation_fee_liquidity_reserve_usdc_minor = fees.liquidity_reserve_usdc_minor;
+    round.cancellation_fee_winner_pot_usdc_minor = fees.winner_pot_usdc_minor;
+}
+
+pub fn stake_split_total(legs: &StakeSplit) -> Result<u64> {
+    let total = legs
+        .artist_pending_usdc_minor
+        .checked_add(legs.platform_fee_usdc_minor)
+        .and_then(|total| total.checked_add(legs.liquidity_reserve_usdc_minor))
+        .and_then(|total| total.checked_add(legs.winner_pot_usdc_minor))
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(total)
+}
+
+/// Returns `(shortfall, surplus)` of an escrow balance against the sum of
+/// its liability legs.
+pub fn escrow_shortfall_and_surplus(
+    escrow_balance_usdc_minor: u64,
+    liabilities: &StakeSplit,
+) -> Result<(u64, u64)> {
+    let total = stake_split_total(liabilities)?;
+    Ok((
+        total.saturating_sub(escrow_balance_usdc_minor),
+        escrow_balance_usdc_minor.saturating_sub(total),
+    ))
+}
+
+/// Refuses payouts while the room escrow holds less than the room's booked
+/// liabilities.
+pub fn require_room_escrow_solvent(room: &Room, escrow_balance_usdc_minor: u64) -> Result<()> {
+    let (shortfall, _) =
+        escrow_shortfall_and_surplus(escrow_balance_usdc_minor, &room_escrow_liabilities(room))?;
+    require!(shortfall == 0, ErrorCode::EscrowLedgerShortfall);
+    Ok(())
+}
+
+pub fn validate_pause_flags(pause_flags: u8) -> Result<()> {
+    require!(pause_flags & !PAUSE_ALL == 0, ErrorCode::InvalidPauseFlags);
+    Ok(())
+}
+
+/// Fails if `flag` is paused protocol-wide or for `room`.
+pub fn require_not_paused(protocol: &ProtocolConfig, room: &Room, flag: u8) -> Result<()> {
+    require!(protocol.pause_flags & flag == 0, ErrorCode::ProtocolPaused);
+    require!(room.pause_flags & flag == 0, ErrorCode::RoomPaused);
+    Ok(())
+}
+
+pub fn validate_batch_size(entry_count: usize) -> Result<()> {
+    require!(
+        entry_count > 0 && entry_count <= MAX_BATCH_ENTRIES,
+        ErrorCode::InvalidBatchSize
+    );
+    Ok(())
+}
+
+/// Placements use `init_if_needed` on the nonce-seeded position, so an
+/// existing account, live or a cancelled tombstone, means the nonce was used.
+pub fn require_new_position(position: &PredictionPosition) -> Result<()> {
+    require_keys_eq!(
+        position.user,
+        Pubkey::default(),
+        ErrorCode::DuplicatePositionNonce
+    );
+    Ok(())
+}
+
+/// Empties a cancelled position while keeping its `user`, so the nonce stays
+/// used. It reads as settled and fully claimed: payout paths skip it and
+/// `close_position` can return its rent once the round is over.
+pub fn tombstone_cancelled_position(position: &mut PredictionPosition) {
+    position.stake_amount_usdc_minor = 0;
+    position.artist_pending_usdc_minor = 0;
+    position.platform_fee_usdc_minor = 0;
+    position.liquidity_reserve_usdc_minor = 0;
+    position.winner_pot_usdc_minor = 0;
+    position.correct_stake_usdc_minor = 0;
+    position.was_correct = false;
+    position.tallied = true;
+    position.usdc_payout_usdc_minor = 0;
+    position.settled = true;
+    position.usdc_claimed = true;
+    position.claimed = false;
+    position.entries.clear();
+}
+
+/// Shard a user's parimutuel predictions land on. Keyed by wallet so one
+/// user's placements always stay on one shard.
+pub fn round_shard_index(user: &Pubkey, shard_count: u8) -> Result<u8> {
+    require!(shard_count > 0, ErrorCode::RoundShardsMissing);
+    Ok(user.to_bytes()[0] % shard_count)
+}
+
 pub fn build_reveal_commit_hash(
     outcome_bitmap: &[u8; REVEAL_BITMAP_BYTES],
     salt: &[u8; 32],
@@ -39,6 +269,57 @@ pub fn build_reveal_commit_hash(
     hashv(&[b"jamming_prediction:round_reveal:v1", outcome_bitmap, salt]).to_bytes()
 }
 
+/// v2 commitments also bind the room, round index and BPM so a commitment
+/// cannot be replayed across rounds and reused patterns are not recognisable.
+pub fn build_reveal_commit_hash_v2(
+    room: &Pubkey,
+    round_index: u64,
+    bpm: u16,
+    outcome_bitmap: &[u8; REVEAL_BITMAP_BYTES],
+    salt: &[u8; 32],
+) -> [u8; 32] {
+    hashv(&[
+        b"jamming_prediction:round_reveal:v2",
+        room.as_ref(),
+        &round_index.to_le_bytes(),
+        &bpm.to_le_bytes(),
+        outcome_bitmap,
+        salt,
+    ])
+    .to_bytes()
+}
+
+/// New commitments must be v2. v1 is only ever verified, for rounds that
+/// were committed with it before the upgrade.
+pub fn validate_commit_version(commit_version: u8) -> Result<()> {
+    require!(
+        commit_version == REVEAL_COMMIT_VERSION_V2,
+        ErrorCode::UnsupportedCommitVersion
+    );
+    Ok(())
+}
+
+pub fn build_versioned_reveal_commit_hash(
+    commit_version: u8,
+    room: &Pubkey,
+    round_index: u64,
+    bpm: u16,
+    outcome_bitmap: &[u8; REVEAL_BITMAP_BYTES],
+    salt: &[u8; 32],
+) -> Result<[u8; 32]> {
+    match commit_version {
+        REVEAL_COMMIT_VERSION_V1 => Ok(build_reveal_commit_hash(outcome_bitmap, salt)),
+        REVEAL_COMMIT_VERSION_V2 => Ok(build_reveal_commit_hash_v2(
+            room,
+            round_index,
+            bpm,
+            outcome_bitmap,
+            salt,
+        )),
+        _ => err!(ErrorCode::UnsupportedCommitVersion),
+    }
+}
+
 pub fn verify_reveal_commit_hash(
     expected_commit_hash: [u8; 32],
     outcome_bitmap: &[u8; REVEAL_BITMAP_BYTES],
@@ -55,13 +336,17 @@ pub fn validate_prediction_indices(track_index: u8, step_index: u8) -> Result<()
     Ok(())
 }
 
+pub fn tile_index(track_index: u8, step_index: u8) -> Result<usize> {
+    validate_prediction_indices(track_index, step_index)?;
+    Ok((track_index as usize) * (MAX_STEPS as usize) + (step_index as usize))
+}
+
 pub fn tile_is_active(
     outcome_bitmap: &[u8; REVEAL_BITMAP_BYTES],
     track_index: u8,
     step_index: u8,
 ) -> Result<bool> {
-    validate_prediction_indices(track_index, step_index)?;
-    let linear = (track_index as usize) * (MAX_STEPS as usize) + (step_index as usize);
+    let linear = tile_index(track_index, step_index)?;
     let byte_index = linear / 8;
     let bit_index = (linear % 8) as u8;
     let mask = 1u8 << bit_index;
@@ -77,6 +362,323 @@ pub fn evaluate_prediction(
     Ok(tile_is_active(outcome_bitmap, track_index, step_index)? == will_be_active)
 }
 
+pub fn validate_market_config(
+    market_mode: MarketMode,
+    lmsr_liquidity_usdc_minor: u64,
+) -> Result<()> {
+    require!(
+        market_mode != MarketMode::Lmsr || lmsr_liquidity_usdc_minor > 0,
+        ErrorCode::InvalidLmsrLiquidity
+    );
+    Ok(())
+}
+
+/// LMSR prices are computed in unsigned Q64.64 fixed point: `FIXED_ONE` is
+/// 1.0 and a value's raw `u128` is the real value times 2^64.
+const FIXED_ONE: u128 = 1 << 64;
+const FIXED_FRACTION_MASK: u128 = FIXED_ONE - 1;
+/// `ln 2` in Q64.64, rounded down and up.
+const LN_2_FIXED_DOWN: u128 = 12_786_308_645_202_655_659;
+const LN_2_FIXED_UP: u128 = LN_2_FIXED_DOWN + 1;
+/// `e^-x` is below one ulp from here on.
+const EXP_NEG_ZERO_FROM: u128 = 45 * FIXED_ONE;
+/// Bound on the error of `lmsr_softplus_fixed`, with wide headroom: the
+/// series and range reduction below stay within a few hundred ulps.
+const LMSR_SOFTPLUS_ERROR_FIXED: u128 = 1 << 12;
+
+/// Q64.64 `x` times the integer `n`, rounded down to an integer.
+fn mul_fixed_int(x: u128, n: u64) -> Result<u128> {
+    let whole = (x >> 64)
+        .checked_mul(n as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let fraction = ((x & FIXED_FRACTION_MASK) * n as u128) >> 64;
+    whole
+        .checked_add(fraction)
+        .ok_or(ErrorCode::MathOverflow.into())
+}
+
+/// `numerator / denominator` in Q64.64, rounded down.
+fn div_to_fixed(numerator: u128, denominator: u64) -> Result<u128> {
+    let denominator = denominator as u128;
+    let whole = numerator / denominator;
+    require!(whole < FIXED_ONE, ErrorCode::MathOverflow);
+    Ok((whole << 64) | (((numerator % denominator) << 64) / denominator))
+}
+
+/// `e^-x` for Q64.64 `x`, rounded down. Reduces `x = k ln 2 + r` and sums
+/// the Taylor series of `e^-r`.
+fn exp_neg_fixed(x: u128) -> u128 {
+    if x >= EXP_NEG_ZERO_FROM {
+        return 0;
+    }
+    let halvings = x / LN_2_FIXED_DOWN;
+    let r = x - halvings * LN_2_FIXED_DOWN;
+
+    let (mut positive, mut negative) = (FIXED_ONE, 0u128);
+    let mut term = FIXED_ONE;
+    let mut n = 1u128;
+    while term > 0 {
+        // r < 1 and term <= 1, so the product fits.
+        term = ((term * r) >> 64) / n;
+        if n % 2 == 1 {
+            negative += term;
+        } else {
+            positive += term;
+        }
+        n += 1;
+    }
+    positive.saturating_sub(negative) >> halvings
+}
+
+/// `ln(1 + t)` for Q64.64 `t` in `[0, 1]`, via
+/// `2 * atanh(t / (2 + t))`, whose series ratio is at most 1/9.
+fn ln_1p_fixed(t: u128) -> u128 {
+    let w = (t << 63) / (FIXED_ONE + (t >> 1));
+    let w_squared = (w * w) >> 64;
+    let mut sum = 0u128;
+    let mut power = w;
+    let mut denominator = 1u128;
+    while power > 0 {
+        sum += power / denominator;
+        power = (power * w_squared) >> 64;
+        denominator += 2;
+    }
+    sum * 2
+}
+
+/// `ln(1 + e^-t)` for Q64.64 `t`: the LMSR cost of a binary market above its
+/// leading side, per unit of liquidity. Always in `[0, ln 2]`, so its error
+/// is absolute and bounded by `LMSR_SOFTPLUS_ERROR_FIXED`.
+fn lmsr_softplus_fixed(t: u128) -> u128 {
+    ln_1p_fixed(exp_neg_fixed(t))
+}
+
+/// LMSR cost `b * ln(e^(q_yes / b) + e^(q_no / b))` of one binary tile
+/// market, in quote minor units, as `(lower, upper)` bounds around the true
+/// value. Written `max(q_yes, q_no) + b * ln(1 + e^(-|q_yes - q_no| / b))` so
+/// only a bounded term is approximated.
+pub fn lmsr_cost_bounds(q_yes: u64, q_no: u64, liquidity_usdc_minor: u64) -> Result<(u128, u128)> {
+    require!(liquidity_usdc_minor > 0, ErrorCode::InvalidLmsrLiquidity);
+    let high = q_yes.max(q_no) as u128;
+    let gap = (q_yes.abs_diff(q_no) as u128) << 64;
+    let t = gap / liquidity_usdc_minor as u128;
+    let softplus = lmsr_softplus_fixed(t);
+
+    let liquidity = liquidity_usdc_minor as u128;
+    let lower = (liquidity * softplus.saturating_sub(LMSR_SOFTPLUS_ERROR_FIXED)) >> 64;
+    let upper_fixed = (softplus + LMSR_SOFTPLUS_ERROR_FIXED).min(LN_2_FIXED_UP);
+    let upper = (liquidity * upper_fixed).div_ceil(FIXED_ONE);
+    Ok((high + lower, high + upper))
+}
+
+/// Current price of one share of a side, as a Q64.64 fraction of its
+/// one-unit payout: `1 / (1 + e^((q_other - q_side) / b))`.
+pub fn lmsr_price(q_side: u64, q_other: u64, liquidity_usdc_minor: u64) -> Result<u128> {
+    require!(liquidity_usdc_minor > 0, ErrorCode::InvalidLmsrLiquidity);
+    let gap = (q_side.abs_diff(q_other) as u128) << 64;
+    let e = exp_neg_fixed(gap / liquidity_usdc_minor as u128);
+    // Price of the side that is ahead, 1 / (1 + e), with e <= 1.
+    let leading = (FIXED_ONE << 63) / ((FIXED_ONE + e) >> 1);
+    Ok(if q_side >= q_other {
+        leading
+    } else {
+        FIXED_ONE - leading
+    })
+}
+
+/// Closed-form guess for `lmsr_shares_for_cost`: solves
+/// `C(q_side + s, q_other) = target` as
+/// `s = q_other + Z - b * -ln(1 - e^(-Z / b)) - q_side` with
+/// `Z = target - q_other`. Only a starting point; the caller checks it.
+fn lmsr_shares_estimate(
+    q_side: u64,
+    q_other: u64,
+    liquidity_usdc_minor: u64,
+    target_cost: u128,
+) -> Result<u64> {
+    let over_other = target_cost
+        .checked_sub(q_other as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let z = match div_to_fixed(over_other, liquidity_usdc_minor) {
+        Ok(z) => z,
+        // Z / b is far past the point where the log term vanishes.
+        Err(_) => EXP_NEG_ZERO_FROM,
+    };
+
+    // v = 1 - e^-z, by series while z < 1 to keep its relative precision.
+    let v = if z < FIXED_ONE {
+        let (mut positive, mut negative) = (0u128, 0u128);
+        let mut term = FIXED_ONE;
+        let mut n = 1u128;
+        loop {
+            term = ((term * z) >> 64) / n;
+            if term == 0 {
+                break;
+            }
+            if n % 2 == 1 {
+                positive += term;
+            } else {
+                negative += term;
+            }
+            n += 1;
+        }
+        positive.saturating_sub(negative)
+    } else {
+        FIXED_ONE - exp_neg_fixed(z)
+    };
+    if v == 0 {
+        return Ok(0);
+    }
+
+    // -ln v, with v = m / 2^k and m in [1, 2).
+    let doublings = v.leading_zeros() - 63;
+    let mantissa = v << doublings;
+    let neg_ln_v =
+        (doublings as u128 * LN_2_FIXED_UP).saturating_sub(ln_1p_fixed(mantissa - FIXED_ONE));
+
+    let next_q_side = (q_other as u128 + over_other)
+        .saturating_sub(mul_fixed_int(neg_ln_v, liquidity_usdc_minor)?);
+    let shares = next_q_side.saturating_sub(q_side as u128);
+    Ok(u64::try_from(shares).unwrap_or(u64::MAX))
+}
+
+/// Shares of one side that `cost_usdc_minor` buys at the current tile state.
+/// Rounds against the buyer: the upper bound of the cost after the purchase
+/// minus the lower bound before it never exceeds `cost_usdc_minor`, so the
+/// exact LMSR cost of the shares is always covered by what was paid.
+pub fn lmsr_shares_for_cost(
+    q_side: u64,
+    q_other: u64,
+    liquidity_usdc_minor: u64,
+    cost_usdc_minor: u64,
+) -> Result<u64> {
+    require!(liquidity_usdc_minor > 0, ErrorCode::InvalidLmsrLiquidity);
+    if cost_usdc_minor == 0 {
+        return Ok(0);
+    }
+
+    let (cost_before, _) = lmsr_cost_bounds(q_side, q_other, liquidity_usdc_minor)?;
+    let budget = cost_before + cost_usdc_minor as u128;
+    let fits = |shares: u64| -> Result<bool> {
+        let Some(next_q_side) = q_side.checked_add(shares) else {
+            return Ok(false);
+        };
+        Ok(lmsr_cost_bounds(next_q_side, q_other, liquidity_usdc_minor)?.1 <= budget)
+    };
+
+    // Back the estimate off slightly so it nearly always fits first time;
+    // the bisection only runs if it does not.
+    let estimate = lmsr_shares_estimate(q_side, q_other, liquidity_usdc_minor, budget)?;
+    let estimate = estimate.saturating_sub((estimate >> 32) + 2);
+    if fits(estimate)? {
+        return Ok(estimate);
+    }
+    let (mut low, mut high) = (0u64, estimate);
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if fits(mid)? {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
+
+/// Quote returned for selling `shares` of one side back to the market.
+/// Rounds against the seller: the lower bound of the cost before the sale
+/// minus the upper bound after it.
+pub fn lmsr_sale_proceeds(
+    q_side: u64,
+    q_other: u64,
+    liquidity_usdc_minor: u64,
+    shares: u64,
+) -> Result<u64> {
+    require!(liquidity_usdc_minor > 0, ErrorCode::InvalidLmsrLiquidity);
+    let remaining_q_side = q_side.checked_sub(shares).ok_or(ErrorCode::MathOverflow)?;
+
+    let (cost_before, _) = lmsr_cost_bounds(q_side, q_other, liquidity_usdc_minor)?;
+    let (_, cost_after) = lmsr_cost_bounds(remaining_q_side, q_other, liquidity_usdc_minor)?;
+    u64::try_from(cost_before.saturating_sub(cost_after))
+        .map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// Worst-case market-maker loss on one tile, `b * ln 2`, rounded up.
+///
+/// No extra headroom is needed: every purchase pays at least the exact cost
+/// change and every sale returns at most it, so what the market holds is at
+/// least `C(q_final) - C(0, 0) = C(q_final) - b ln 2`. A winning side pays
+/// `q_side <= C(q_final)`, which that plus the subsidy covers.
+pub fn lmsr_tile_subsidy(liquidity_usdc_minor: u64) -> Result<u64> {
+    let subsidy = (liquidity_usdc_minor as u128 * LN_2_FIXED_UP).div_ceil(FIXED_ONE);
+    u64::try_from(subsidy).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// Legs returned when a position is cancelled: each leg minus its share of
+/// the cancellation fee. The retained part stays in the escrow on its leg.
+pub fn cancellation_refund(legs: &StakeSplit, cancellation_fee_bps: u16) -> Result<StakeSplit> {
+    let refund_leg = |leg: u64| -> Result<u64> {
+        leg.checked_sub(split_amount(leg, cancellation_fee_bps)?)
+            .ok_or(ErrorCode::MathOverflow.into())
+    };
+
+    Ok(StakeSplit {
+        artist_pending_usdc_minor: refund_leg(legs.artist_pending_usdc_minor)?,
+        platform_fee_usdc_minor: refund_leg(legs.platform_fee_usdc_minor)?,
+        liquidity_reserve_usdc_minor: refund_leg(legs.liquidity_reserve_usdc_minor)?,
+        winner_pot_usdc_minor: refund_leg(legs.winner_pot_usdc_minor)?,
+    })
+}
+
+/// Parimutuel payout on one tile: a winner gets its own winner-pot leg back
+/// plus a share of the losing side's pool proportional to that leg.
+pub fn parimutuel_tile_payout(
+    own_pool_usdc_minor: u64,
+    winning_pool_usdc_minor: u64,
+    losing_pool_usdc_minor: u64,
+) -> Result<u64> {
+    if winning_pool_usdc_minor == 0 {
+        return Ok(own_pool_usdc_minor);
+    }
+
+    let winnings = (losing_pool_usdc_minor as u128)
+        .checked_mul(own_pool_usdc_minor as u128)
+        .and_then(|v| v.checked_div(winning_pool_usdc_minor as u128))
+        .ok_or(ErrorCode::MathOverflow)?;
+    let winnings = u64::try_from(winnings).map_err(|_| ErrorCode::MathOverflow)?;
+    own_pool_usdc_minor
+        .checked_add(winnings)
+        .ok_or(ErrorCode::MathOverflow.into())
+}
+
+/// Pro-rata share of the winner pot for one correct position. The last winner
+/// to settle takes whatever is left so flooring dust never strands in the pot.
+pub fn pro_rata_winner_payout(
+    winner_pot_usdc_minor: u64,
+    distributed_usdc_minor: u64,
+    stake_usdc_minor: u64,
+    correct_stake_usdc_minor: u64,
+    is_last_winner: bool,
+) -> Result<u64> {
+    let remaining = winner_pot_usdc_minor
+        .checked_sub(distributed_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if is_last_winner {
+        return Ok(remaining);
+    }
+    if correct_stake_usdc_minor == 0 {
+        return Ok(0);
+    }
+
+    let share = (winner_pot_usdc_minor as u128)
+        .checked_mul(stake_usdc_minor as u128)
+        .and_then(|v| v.checked_div(correct_stake_usdc_minor as u128))
+        .ok_or(ErrorCode::MathOverflow)?;
+    let share = u64::try_from(share).map_err(|_| ErrorCode::MathOverflow)?;
+    require!(share <= remaining, ErrorCode::PayoutExceedsWinnerPot);
+    Ok(share)
+}
+
 pub fn transfer_quote_from_user<'info>(
     authority: &Signer<'info>,
     from: &Account<'info, TokenAccount>,
@@ -110,6 +712,27 @@ pub fn transfer_quote_with_authority<'info>(
     token::transfer_checked(cpi_ctx, amount, mint.decimals)
 }
 
+/// Moves a whole stake into an escrow (the room's, or a round shard's) in
+/// one CPI. The legs only exist in the ledgers; see `credit_room_escrow_ledger`.
+pub fn transfer_stake_to_escrow<'info>(
+    authority: &Signer<'info>,
+    from: &Account<'info, TokenAccount>,
+    escrow: &Account<'info, TokenAccount>,
+    mint: &Account<'info, Mint>,
+    token_program: &Program<'info, Token>,
+    split: &StakeSplit,
+) -> Result<()> {
+    transfer_quote_with_authority(
+        authority,
+        from,
+        escrow,
+        mint,
+        token_program,
+        stake_split_total(split)?,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn transfer_quote_from_vault<'info>(
     room: &Account<'info, Room>,
     vault_authority_bump: u8,
@@ -146,6 +769,69 @@ pub fn transfer_quote_from_vault<'info>(
     token::transfer_checked(cpi_ctx, amount, mint.decimals)
 }
 
+/// Closes an emptied token account owned by the room's vault authority.
+pub fn close_vault_token_account<'info>(
+    room: &Account<'info, Room>,
+    vault_authority_bump: u8,
+    vault_authority: &UncheckedAccount<'info>,
+    account: &Account<'info, TokenAccount>,
+    destination: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let room_key = room.key();
+    let signer_seeds: &[&[u8]] = &[
+        b"vault_authority",
+        room_key.as_ref(),
+        &[vault_authority_bump],
+    ];
+
+    let cpi_accounts = CloseAccount {
+        account: account.to_account_info(),
+        destination: destination.clone(),
+        authority: vault_authority.to_account_info(),
+    };
+    let signer_binding = [signer_seeds];
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        cpi_accounts,
+        &signer_binding,
+    );
+    token::close_account(cpi_ctx)
+}
+
+pub fn transfer_quote_from_treasury<'info>(
+    protocol_treasury: &Account<'info, ProtocolTreasury>,
+    from: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    mint: &Account<'info, Mint>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let signer_seeds: &[&[u8]] = &[
+        b"treasury",
+        protocol_treasury.protocol.as_ref(),
+        &[protocol_treasury.bump],
+    ];
+
+    let cpi_accounts = TransferChecked {
+        from: from.to_account_info(),
+        to: to.to_account_info(),
+        authority: protocol_treasury.to_account_info(),
+        mint: mint.to_account_info(),
+    };
+    let signer_binding = [signer_seeds];
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        cpi_accounts,
+        &signer_binding,
+    );
+    token::transfer_checked(cpi_ctx, amount, mint.decimals)
+}
+
 pub fn mint_reward_from_vault_authority<'info>(
     room: &Account<'info, Room>,
     vault_authority_bump: u8,
@@ -197,6 +883,30 @@ pub fn validate_delegated_prediction_signer(
     Ok(())
 }
 
+/// Round-wide delegated spend after adding `stake_amount_usdc_minor`. The cap
+/// is kept on `Round` rather than per shard so it does not depend on which
+/// shard a user lands on.
+pub fn delegated_round_spend(
+    delegated_spent_usdc_minor: u64,
+    stake_amount_usdc_minor: u64,
+    max_delegate_stake_usdc_minor: u64,
+) -> Result<u64> {
+    let delegated_spent_next = delegated_spent_usdc_minor
+        .checked_add(stake_amount_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(
+        delegated_spent_next <= max_delegate_stake_usdc_minor,
+        ErrorCode::DelegatedRoundCapExceeded
+    );
+    Ok(delegated_spent_next)
+}
+
+/// The artist and registered operators know the committed pattern, so they
+/// may not hold positions in their own room.
+pub fn is_room_excluded_wallet(artist: Pubkey, operators: &[Pubkey], wallet: Pubkey) -> bool {
+    wallet == artist || operators.contains(&wallet)
+}
+
 pub fn one_token_amount(decimals: u8) -> Result<u64> {
     10u64
         .checked_pow(decimals as u32)
@@ -216,8 +926,33 @@ pub fn is_valid_mint_freeze_authority(
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
 
+    #[test]
+    fn escrow_shortfall_and_surplus_compares_against_all_legs() {
+        let liabilities = StakeSplit {
+            artist_pending_usdc_minor: 10,
+            platform_fee_usdc_minor: 20,
+            liquidity_reserve_usdc_minor: 30,
+            winner_pot_usdc_minor: 40,
+        };
+
+        assert_eq!(
+            escrow_shortfall_and_surplus(100, &liabilities).unwrap(),
+            (0, 0)
+        );
+        assert_eq!(
+            escrow_shortfall_and_surplus(95, &liabilities).unwrap(),
+            (5, 0)
+        );
+        assert_eq!(
+            escrow_shortfall_and_surplus(120, &liabilities).unwrap(),
+            (0, 20)
+        );
+    }
+
     #[test]
     fn split_amount_computes_expected_values() {
         assert_eq!(split_amount(10_000, 500).unwrap(), 500);
@@ -230,7 +965,459 @@ mod tests {
         let err = split_amount(u64::MAX, 10_000).unwrap_err();
         match err {
             anchor_lang::error::Error::AnchorError(anchor_err) => {
-                assert_eq!(anchor_err.error_code_number, ErrorCode::MathOverflow as u32);
+                assert_eq!(
+                    anchor_err.error_code_number,
+                    u32::from(ErrorCode::MathOverflow)
+                );
+            }
+            other => panic!("unexpected error variant: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn split_stake_gives_winner_pot_the_remainder() {
+        let (split, dust) =
+            split_stake(1_001, 5_000, 500, 1_500, 3_000, DustDestination::WinnerPot).unwrap();
+        assert_eq!(
+            split,
+            StakeSplit {
+                artist_pending_usdc_minor: 500,
+                platform_fee_usdc_minor: 50,
+                liquidity_reserve_usdc_minor: 150,
+                winner_pot_usdc_minor: 301,
+            }
+        );
+        assert_eq!(dust, 1);
+    }
+
+    #[test]
+    fn split_stake_routes_dust_to_configured_leg() {
+        let (split, dust) =
+            split_stake(7, 2_500, 2_500, 2_500, 2_500, DustDestination::PlatformFee).unwrap();
+        assert_eq!(
+            split,
+            StakeSplit {
+                artist_pending_usdc_minor: 1,
+                platform_fee_usdc_minor: 4,
+                liquidity_reserve_usdc_minor: 1,
+                winner_pot_usdc_minor: 1,
+            }
+        );
+        assert_eq!(dust, 3);
+    }
+
+    fn dust_destination_strategy() -> impl Strategy<Value = DustDestination> {
+        prop_oneof![
+            Just(DustDestination::WinnerPot),
+            Just(DustDestination::ArtistPending),
+            Just(DustDestination::PlatformFee),
+            Just(DustDestination::LiquidityReserve),
+        ]
+    }
+
+    /// Any four bps values summing to 10_000, i.e. every split accepted by
+    /// `validate_fee_split`: three sorted cut points over 0..=10_000.
+    fn fee_split_strategy() -> impl Strategy<Value = (u16, u16, u16, u16)> {
+        prop::array::uniform3(0u16..=10_000).prop_map(|mut cuts| {
+            cuts.sort_unstable();
+            (
+                cuts[0],
+                cuts[1] - cuts[0],
+                cuts[2] - cuts[1],
+                10_000 - cuts[2],
+            )
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn split_stake_legs_always_sum_to_stake(
+            (artist_bps, platform_bps, liquidity_bps, winner_bps) in fee_split_strategy(),
+            stake in prop_oneof![0u64..1_000, 0u64..=u64::MAX / 10_000],
+            dust_destination in dust_destination_strategy(),
+        ) {
+            prop_assert!(
+                validate_fee_split(platform_bps, artist_bps, liquidity_bps, winner_bps).is_ok()
+            );
+
+            let (split, dust) = split_stake(
+                stake,
+                artist_bps,
+                platform_bps,
+                liquidity_bps,
+                winner_bps,
+                dust_destination,
+            )
+            .unwrap();
+
+            prop_assert_eq!(stake_split_total(&split).unwrap(), stake);
+            prop_assert!(dust <= 3);
+
+            let floors = StakeSplit {
+                artist_pending_usdc_minor: split_amount(stake, artist_bps).unwrap(),
+                platform_fee_usdc_minor: split_amount(stake, platform_bps).unwrap(),
+                liquidity_reserve_usdc_minor: split_amount(stake, liquidity_bps).unwrap(),
+                winner_pot_usdc_minor: split_amount(stake, winner_bps).unwrap(),
+            };
+            let mut expected = floors;
+            match dust_destination {
+                DustDestination::WinnerPot => expected.winner_pot_usdc_minor += dust,
+                DustDestination::ArtistPending => expected.artist_pending_usdc_minor += dust,
+                DustDestination::PlatformFee => expected.platform_fee_usdc_minor += dust,
+                DustDestination::LiquidityReserve => {
+                    expected.liquidity_reserve_usdc_minor += dust
+                }
+            }
+            prop_assert_eq!(split, expected);
+        }
+
+        #[test]
+        fn lmsr_collected_cost_plus_subsidy_covers_payout_over_many_trades(
+            liquidity in prop_oneof![1u64..1_000, 1_000u64..1_000_000_000_000],
+            trades in prop::collection::vec(
+                (any::<bool>(), any::<bool>(), prop_oneof![1u64..1_000, 1u64..50_000_000_000_000]),
+                1..40,
+            ),
+        ) {
+            let (mut q_yes, mut q_no, mut collected) = (0u64, 0u64, 0i128);
+            for (yes, buy, amount) in trades {
+                let (q_side, q_other) = if yes { (q_yes, q_no) } else { (q_no, q_yes) };
+                let next_q_side = if buy {
+                    collected += amount as i128;
+                    q_side + lmsr_shares_for_cost(q_side, q_other, liquidity, amount).unwrap()
+                } else {
+                    let shares = amount % (q_side + 1);
+                    collected -=
+                        lmsr_sale_proceeds(q_side, q_other, liquidity, shares).unwrap() as i128;
+                    q_side - shares
+                };
+                if yes {
+                    q_yes = next_q_side;
+                } else {
+                    q_no = next_q_side;
+                }
+
+                let subsidy = lmsr_tile_subsidy(liquidity).unwrap() as i128;
+                prop_assert!(collected + subsidy >= q_yes.max(q_no) as i128);
+            }
+        }
+    }
+
+    #[test]
+    fn parimutuel_tile_payout_shares_losing_pool_by_own_leg() {
+        assert_eq!(parimutuel_tile_payout(300, 900, 600).unwrap(), 500);
+        assert_eq!(parimutuel_tile_payout(600, 900, 600).unwrap(), 1_000);
+        assert_eq!(parimutuel_tile_payout(300, 300, 0).unwrap(), 300);
+    }
+
+    #[test]
+    fn lmsr_shares_for_cost_prices_an_empty_tile_at_one_half() {
+        let shares = lmsr_shares_for_cost(0, 0, 1_000_000, 1_000).unwrap();
+        assert!((1_990..=2_000).contains(&shares));
+        assert!(lmsr_price(shares, 0, 1_000_000).unwrap() > FIXED_ONE / 2);
+        assert!(lmsr_price(0, shares, 1_000_000).unwrap() < FIXED_ONE / 2);
+    }
+
+    #[test]
+    fn lmsr_collected_cost_plus_subsidy_covers_every_outcome() {
+        let liquidity = 50_000;
+        let (mut q_yes, mut q_no, mut collected) = (0u64, 0u64, 0u64);
+        for (buy_yes, cost) in [
+            (true, 10_000),
+            (true, 250_000),
+            (false, 7),
+            (false, 90_000),
+            (true, 1),
+            (true, 3_000_000),
+        ] {
+            if buy_yes {
+                q_yes += lmsr_shares_for_cost(q_yes, q_no, liquidity, cost).unwrap();
+            } else {
+                q_no += lmsr_shares_for_cost(q_no, q_yes, liquidity, cost).unwrap();
+            }
+            collected += cost;
+        }
+
+        let subsidy = lmsr_tile_subsidy(liquidity).unwrap();
+        assert!(collected + subsidy >= q_yes.max(q_no));
+    }
+
+    #[test]
+    fn lmsr_sale_proceeds_never_exceed_purchase_cost() {
+        let liquidity = 1_000_000;
+        let shares = lmsr_shares_for_cost(5_000, 2_000, liquidity, 40_000).unwrap();
+        let proceeds = lmsr_sale_proceeds(5_000 + shares, 2_000, liquidity, shares).unwrap();
+        assert!(proceeds <= 40_000);
+        assert!(proceeds >= 39_990);
+        assert!(lmsr_sale_proceeds(10, 0, liquidity, 11).is_err());
+    }
+
+    #[test]
+    fn lmsr_cost_bounds_bracket_the_exact_cost() {
+        for (q_yes, q_no, liquidity) in [
+            (0u64, 0u64, 1_000_000u64),
+            (2_500_000, 1_000_000, 1_000_000),
+            (1_000_000, 2_500_000, 1_000_000),
+            (7, 90_000, 3_000),
+            (40_000_000, 0, 1_000_000),
+        ] {
+            let (lower, upper) = lmsr_cost_bounds(q_yes, q_no, liquidity).unwrap();
+            let b = liquidity as f64;
+            let exact = b * ((q_yes as f64 / b).exp() + (q_no as f64 / b).exp()).ln();
+            assert!(lower as f64 <= exact + 1e-6, "{lower} > {exact}");
+            assert!(upper as f64 >= exact - 1e-6, "{upper} < {exact}");
+            assert!(upper - lower <= 2);
+        }
+    }
+
+    #[test]
+    fn lmsr_round_trip_never_profits_at_large_quantities() {
+        let liquidity = 1_000_000;
+        for (q_yes, q_no) in [
+            (50_000_000_000_000u64, 50_000_000_000_000u64),
+            (90_000_000_000_000, 10),
+            (10, 90_000_000_000_000),
+            (u64::MAX / 4, u64::MAX / 4 - 3_000_000),
+        ] {
+            for cost in [1u64, 999, 1_000_000, 20_000_000_000_000] {
+                let shares = lmsr_shares_for_cost(q_yes, q_no, liquidity, cost).unwrap();
+                let proceeds = lmsr_sale_proceeds(q_yes + shares, q_no, liquidity, shares).unwrap();
+                assert!(proceeds <= cost, "{proceeds} > {cost}");
+            }
+        }
+    }
+
+    #[test]
+    fn cancel_round_releases_fees_kept_from_cancelled_positions() {
+        let (kept_position, _) =
+            split_stake(10_000, 5_000, 500, 1_500, 3_000, DustDestination::WinnerPot).unwrap();
+        let (cancelled_position, _) =
+            split_stake(7_777, 5_000, 500, 1_500, 3_000, DustDestination::WinnerPot).unwrap();
+        let mut round_legs = add_stake_splits(&kept_position, &cancelled_position).unwrap();
+
+        // cancel_prediction refunds the legs less the fee and keeps the rest.
+        let refund = cancellation_refund(&cancelled_position, 250).unwrap();
+        round_legs = sub_stake_splits(&round_legs, &refund).unwrap();
+        let kept_fees = sub_stake_splits(&cancelled_position, &refund).unwrap();
+        assert!(stake_split_total(&kept_fees).unwrap() > 0);
+
+        // cancel_round releases the kept fees, leaving exactly what
+        // refund_position returns to the open position.
+        let refundable = sub_stake_splits(&round_legs, &kept_fees).unwrap();
+        assert_eq!(refundable, kept_position);
+        assert_eq!(
+            stake_split_total(&refund).unwrap()
+                + stake_split_total(&kept_fees).unwrap()
+                + stake_split_total(&refundable).unwrap(),
+            17_777
+        );
+    }
+
+    #[test]
+    fn sub_stake_splits_rejects_underflow_on_any_leg() {
+        let a = StakeSplit {
+            winner_pot_usdc_minor: 5,
+            ..StakeSplit::default()
+        };
+        let b = StakeSplit {
+            platform_fee_usdc_minor: 1,
+            ..StakeSplit::default()
+        };
+        assert!(sub_stake_splits(&a, &b).is_err());
+        assert_eq!(sub_stake_splits(&a, &a).unwrap(), StakeSplit::default());
+    }
+
+    #[test]
+    fn cancelled_position_keeps_its_nonce_used() {
+        let user = Pubkey::new_unique();
+        let mut position = PredictionPosition {
+            round: Pubkey::new_unique(),
+            user: Pubkey::default(),
+            client_nonce: 0,
+            stake_amount_usdc_minor: 0,
+            artist_pending_usdc_minor: 0,
+            platform_fee_usdc_minor: 0,
+            liquidity_reserve_usdc_minor: 0,
+            winner_pot_usdc_minor: 0,
+            correct_stake_usdc_minor: 0,
+            was_correct: false,
+            tallied: false,
+            usdc_payout_usdc_minor: 0,
+            settled: false,
+            usdc_claimed: false,
+            claimed: false,
+            bump: 255,
+            entries: Vec::new(),
+        };
+        require_new_position(&position).unwrap();
+
+        // Placement records the position, then the user cancels it.
+        position.user = user;
+        position.client_nonce = 42;
+        position.stake_amount_usdc_minor = 10_000;
+        position.winner_pot_usdc_minor = 3_000;
+        position.entries.push(crate::state::PositionEntry {
+            track_index: 1,
+            step_index: 2,
+            will_be_active: true,
+            stake_amount_usdc_minor: 10_000,
+            winner_pot_usdc_minor: 3_000,
+            shares: 0,
+            was_correct: false,
+        });
+        tombstone_cancelled_position(&mut position);
+
+        assert_eq!(position.user, user);
+        assert_eq!(position.client_nonce, 42);
+        assert_eq!(position.stake_amount_usdc_minor, 0);
+        assert!(position.entries.is_empty());
+        assert!(position.settled && position.usdc_claimed && position.tallied);
+
+        // Resending the same placement still hits the tombstone.
+        match require_new_position(&position).unwrap_err() {
+            anchor_lang::error::Error::AnchorError(anchor_err) => assert_eq!(
+                anchor_err.error_code_number,
+                u32::from(ErrorCode::DuplicatePositionNonce)
+            ),
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn add_stake_splits_sums_each_leg() {
+        let a = split_stake(10_000, 5_000, 500, 1_500, 3_000, DustDestination::WinnerPot)
+            .unwrap()
+            .0;
+        let b = split_stake(333, 5_000, 500, 1_500, 3_000, DustDestination::WinnerPot)
+            .unwrap()
+            .0;
+        let total = add_stake_splits(&a, &b).unwrap();
+        assert_eq!(
+            total,
+            StakeSplit {
+                artist_pending_usdc_minor: 5_166,
+                platform_fee_usdc_minor: 516,
+                liquidity_reserve_usdc_minor: 1_549,
+                winner_pot_usdc_minor: 3_102,
+            }
+        );
+    }
+
+    #[test]
+    fn validate_pause_flags_rejects_unknown_bits() {
+        assert!(validate_pause_flags(0).is_ok());
+        assert!(validate_pause_flags(PAUSE_ALL).is_ok());
+        match validate_pause_flags(PAUSE_ALL + 1).unwrap_err() {
+            anchor_lang::error::Error::AnchorError(anchor_err) => {
+                assert_eq!(
+                    anchor_err.error_code_number,
+                    u32::from(ErrorCode::InvalidPauseFlags)
+                );
+            }
+            other => panic!("unexpected error variant: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn round_shard_index_keeps_each_user_on_one_shard() {
+        let user = Pubkey::new_from_array([13; 32]);
+        assert_eq!(round_shard_index(&user, 8).unwrap(), 5);
+        assert_eq!(round_shard_index(&user, 1).unwrap(), 0);
+        for shard_count in 1..=crate::constants::MAX_ROUND_SHARDS {
+            for first_byte in [0u8, 7, 200, 255] {
+                let mut bytes = [1u8; 32];
+                bytes[0] = first_byte;
+                let index = round_shard_index(&Pubkey::new_from_array(bytes), shard_count).unwrap();
+                assert!(index < shard_count);
+            }
+        }
+
+        match round_shard_index(&user, 0).unwrap_err() {
+            anchor_lang::error::Error::AnchorError(anchor_err) => {
+                assert_eq!(
+                    anchor_err.error_code_number,
+                    u32::from(ErrorCode::RoundShardsMissing)
+                );
+            }
+            other => panic!("unexpected error variant: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn validate_batch_size_bounds() {
+        assert!(validate_batch_size(1).is_ok());
+        assert!(validate_batch_size(MAX_BATCH_ENTRIES).is_ok());
+        for count in [0, MAX_BATCH_ENTRIES + 1] {
+            match validate_batch_size(count).unwrap_err() {
+                anchor_lang::error::Error::AnchorError(anchor_err) => {
+                    assert_eq!(
+                        anchor_err.error_code_number,
+                        u32::from(ErrorCode::InvalidBatchSize)
+                    );
+                }
+                other => panic!("unexpected error variant: {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn cancellation_refund_retains_fee_from_every_leg() {
+        let legs = split_stake(10_000, 5_000, 500, 1_500, 3_000, DustDestination::WinnerPot)
+            .unwrap()
+            .0;
+        let refund = cancellation_refund(&legs, 200).unwrap();
+        assert_eq!(
+            refund,
+            StakeSplit {
+                artist_pending_usdc_minor: 4_900,
+                platform_fee_usdc_minor: 490,
+                liquidity_reserve_usdc_minor: 1_470,
+                winner_pot_usdc_minor: 2_940,
+            }
+        );
+        assert_eq!(cancellation_refund(&legs, 0).unwrap(), legs);
+    }
+
+    #[test]
+    fn lmsr_shares_for_cost_rejects_zero_liquidity() {
+        let err = lmsr_shares_for_cost(0, 0, 0, 1_000).unwrap_err();
+        match err {
+            anchor_lang::error::Error::AnchorError(anchor_err) => {
+                assert_eq!(
+                    anchor_err.error_code_number,
+                    u32::from(ErrorCode::InvalidLmsrLiquidity)
+                );
+            }
+            other => panic!("unexpected error variant: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn tile_index_is_track_major() {
+        assert_eq!(tile_index(0, 0).unwrap(), 0);
+        assert_eq!(tile_index(1, 2).unwrap(), MAX_STEPS as usize + 2);
+        assert!(tile_index(0, MAX_STEPS).is_err());
+    }
+
+    #[test]
+    fn pro_rata_winner_payout_splits_by_stake_and_last_winner_takes_remainder() {
+        let first = pro_rata_winner_payout(1_000, 0, 1, 3, false).unwrap();
+        assert_eq!(first, 333);
+        let second = pro_rata_winner_payout(1_000, first, 1, 3, false).unwrap();
+        assert_eq!(second, 333);
+        let last = pro_rata_winner_payout(1_000, first + second, 1, 3, true).unwrap();
+        assert_eq!(last, 334);
+    }
+
+    #[test]
+    fn pro_rata_winner_payout_rejects_over_distributed_pot() {
+        let err = pro_rata_winner_payout(1_000, 900, 2, 3, false).unwrap_err();
+        match err {
+            anchor_lang::error::Error::AnchorError(anchor_err) => {
+                assert_eq!(
+                    anchor_err.error_code_number,
+                    u32::from(ErrorCode::PayoutExceedsWinnerPot)
+                );
             }
             other => panic!("unexpected error variant: {other:?}"),
         }
@@ -259,6 +1446,86 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn reveal_commit_hash_v2_binds_room_round_and_bpm() {
+        let room = Pubkey::new_unique();
+        let reveal = [7u8; REVEAL_BITMAP_BYTES];
+        let salt = [42u8; 32];
+        let commit_hash = build_reveal_commit_hash_v2(&room, 3, 120, &reveal, &salt);
+
+        assert_eq!(
+            build_versioned_reveal_commit_hash(
+                REVEAL_COMMIT_VERSION_V2,
+                &room,
+                3,
+                120,
+                &reveal,
+                &salt
+            )
+            .unwrap(),
+            commit_hash
+        );
+        assert_ne!(
+            build_reveal_commit_hash_v2(&Pubkey::new_unique(), 3, 120, &reveal, &salt),
+            commit_hash
+        );
+        assert_ne!(
+            build_reveal_commit_hash_v2(&room, 4, 120, &reveal, &salt),
+            commit_hash
+        );
+        assert_ne!(
+            build_reveal_commit_hash_v2(&room, 3, 121, &reveal, &salt),
+            commit_hash
+        );
+        assert_ne!(build_reveal_commit_hash(&reveal, &salt), commit_hash);
+    }
+
+    #[test]
+    fn validate_commit_version_accepts_only_v2() {
+        assert!(validate_commit_version(REVEAL_COMMIT_VERSION_V2).is_ok());
+        for commit_version in [0, REVEAL_COMMIT_VERSION_V1, 3] {
+            match validate_commit_version(commit_version).unwrap_err() {
+                anchor_lang::error::Error::AnchorError(anchor_err) => {
+                    assert_eq!(
+                        anchor_err.error_code_number,
+                        u32::from(ErrorCode::UnsupportedCommitVersion)
+                    );
+                }
+                other => panic!("unexpected error variant: {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn versioned_reveal_commit_hash_keeps_v1_and_rejects_unknown_versions() {
+        let room = Pubkey::new_unique();
+        let reveal = [7u8; REVEAL_BITMAP_BYTES];
+        let salt = [42u8; 32];
+
+        assert_eq!(
+            build_versioned_reveal_commit_hash(
+                REVEAL_COMMIT_VERSION_V1,
+                &room,
+                3,
+                120,
+                &reveal,
+                &salt
+            )
+            .unwrap(),
+            build_reveal_commit_hash(&reveal, &salt)
+        );
+        let err = build_versioned_reveal_commit_hash(0, &room, 3, 120, &reveal, &salt).unwrap_err();
+        match err {
+            anchor_lang::error::Error::AnchorError(anchor_err) => {
+                assert_eq!(
+                    anchor_err.error_code_number,
+                    u32::from(ErrorCode::UnsupportedCommitVersion)
+                );
+            }
+            other => panic!("unexpected error variant: {other:?}"),
+        }
+    }
+
     #[test]
     fn evaluate_prediction_respects_bitmap_state() {
         let mut bitmap = [0u8; REVEAL_BITMAP_BYTES];
@@ -275,7 +1542,7 @@ mod tests {
             anchor_lang::error::Error::AnchorError(anchor_err) => {
                 assert_eq!(
                     anchor_err.error_code_number,
-                    ErrorCode::InvalidPredictionTile as u32
+                    u32::from(ErrorCode::InvalidPredictionTile)
                 );
             }
             other => panic!("unexpected error variant: {other:?}"),
@@ -297,7 +1564,7 @@ mod tests {
             anchor_lang::error::Error::AnchorError(anchor_err) => {
                 assert_eq!(
                     anchor_err.error_code_number,
-                    ErrorCode::InvalidDelegatedPredictionSigner as u32
+                    u32::from(ErrorCode::InvalidDelegatedPredictionSigner)
                 );
             }
             other => panic!("unexpected error variant: {other:?}"),
@@ -312,11 +1579,57 @@ mod tests {
             anchor_lang::error::Error::AnchorError(anchor_err) => {
                 assert_eq!(
                     anchor_err.error_code_number,
-                    ErrorCode::DelegatedStakeCapExceeded as u32
+                    u32::from(ErrorCode::DelegatedStakeCapExceeded)
+                );
+            }
+            other => panic!("unexpected error variant: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn delegated_round_spend_caps_the_round_across_shards() {
+        let shard_count = 4u8;
+        let users: Vec<Pubkey> = (0..shard_count)
+            .map(|first_byte| Pubkey::new_from_array([first_byte; 32]))
+            .collect();
+        for (index, user) in users.iter().enumerate() {
+            assert_eq!(
+                usize::from(round_shard_index(user, shard_count).unwrap()),
+                index
+            );
+        }
+
+        // One shard may take more than an even share of the cap...
+        let mut spent = delegated_round_spend(0, 700, 1_000).unwrap();
+        // ...and the other shards draw on what is left of the same cap.
+        for _ in &users[1..] {
+            spent = delegated_round_spend(spent, 100, 1_000).unwrap();
+        }
+        assert_eq!(spent, 1_000);
+        match delegated_round_spend(spent, 1, 1_000).unwrap_err() {
+            anchor_lang::error::Error::AnchorError(anchor_err) => {
+                assert_eq!(
+                    anchor_err.error_code_number,
+                    u32::from(ErrorCode::DelegatedRoundCapExceeded)
                 );
             }
             other => panic!("unexpected error variant: {other:?}"),
         }
+
+        // A cap smaller than the shard count is still usable.
+        assert_eq!(delegated_round_spend(0, 3, 3).unwrap(), 3);
+    }
+
+    #[test]
+    fn is_room_excluded_wallet_covers_artist_and_operators_only() {
+        let artist = Pubkey::new_unique();
+        let operator = Pubkey::new_unique();
+        let player = Pubkey::new_unique();
+        let operators = [operator];
+
+        assert!(is_room_excluded_wallet(artist, &operators, artist));
+        assert!(is_room_excluded_wallet(artist, &operators, operator));
+        assert!(!is_room_excluded_wallet(artist, &operators, player));
     }
 
     #[test]
@@ -330,7 +1643,10 @@ mod tests {
         let err = one_token_amount(20).unwrap_err();
         match err {
             anchor_lang::error::Error::AnchorError(anchor_err) => {
-                assert_eq!(anchor_err.error_code_number, ErrorCode::MathOverflow as u32);
+                assert_eq!(
+                    anchor_err.error_code_number,
+                    u32::from(ErrorCode::MathOverflow)
+                );
             }
             other => panic!("unexpected error variant: {other:?}"),
         }
@@ -1,16 +1,32 @@
 use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address,
+    token::{Mint, Token, TokenAccount},
+};
 
 use crate::{
-    constants::REVEAL_BITMAP_BYTES,
+    constants::{
+        MAX_ROOM_OPERATORS, MAX_ROUND_SHARDS, PAUSE_CLAIMS, PAUSE_LIQUIDITY, PAUSE_PREDICTIONS,
+        PAUSE_SETTLEMENT, REVEAL_BITMAP_BYTES, TILE_COUNT,
+    },
     contexts::*,
     error::ErrorCode,
     events::{
-        LiquidityReserveDeployed, PositionSettled, PredictionPlaced, RewardTokenClaimed,
-        RoundSettled,
+        AdminProposed, AdminTransferred, ArtistBondDeposited, ArtistBondSlashed,
+        ArtistBondWithdrawn, ArtistPendingClaimed, LiquidityReserveDeployed, PlatformFeeClaimed,
+        PlatformFeeSweepSkipped, PlatformFeesSwept, PositionRefunded, PositionSettled,
+        PredictionBlocked, PredictionCancelled, PredictionDelegateUpdated, PredictionPlaced,
+        ProtocolConfigCancelled, ProtocolConfigExecuted, ProtocolConfigProposed,
+        ProtocolPauseFlagsUpdated, ProtocolRolesUpdated, RewardTokenClaimed, RoomEscrowReconciled,
+        RoomFeeOverrideRemoved, RoomFeeOverrideSet, RoomPauseFlagsUpdated, RoundCancelled,
+        RoundSettled, TileSharesBought, TreasuryWithdrawn, WinningsClaimed,
     },
     helpers::*,
     params::*,
-    state::RoundPhase,
+    state::{
+        EscrowReconciliation, MarketMode, PositionEntry, PredictionPosition, ProtocolConfig, Room,
+        Round, RoundPhase, RoundShard,
+    },
 };
 
 pub fn initialize_protocol(
@@ -27,9 +43,31 @@ pub fn initialize_protocol(
         params.max_stake_usdc_minor >= params.min_stake_usdc_minor,
         ErrorCode::InvalidStakeRange
     );
+    require!(
+        params.reveal_timeout_seconds > 0,
+        ErrorCode::InvalidRevealTimeout
+    );
+    require!(
+        params.artist_bond_slash_bps <= 10_000 && params.bond_withdrawal_cooldown_seconds >= 0,
+        ErrorCode::InvalidArtistBondConfig
+    );
+    require!(
+        params.cancellation_fee_bps <= 10_000,
+        ErrorCode::InvalidCancellationFee
+    );
+    require!(
+        params.config_timelock_seconds >= 0,
+        ErrorCode::InvalidConfigTimelock
+    );
 
     let protocol = &mut ctx.accounts.protocol;
-    protocol.admin = ctx.accounts.admin.key();
+    let admin = ctx.accounts.admin.key();
+    protocol.admin = admin;
+    protocol.pauser = admin;
+    protocol.fee_manager = admin;
+    protocol.treasury = admin;
+    protocol.liquidity_deployer = admin;
+    protocol.delegate_manager = admin;
     protocol.quote_mint = params.quote_mint;
     protocol.platform_fee_bps = params.platform_fee_bps;
     protocol.artist_pending_bps = params.artist_pending_bps;
@@ -40,13 +78,22 @@ pub fn initialize_protocol(
     protocol.min_launch_quote_usdc_minor = params.min_launch_quote_usdc_minor;
     protocol.prediction_delegate = params.prediction_delegate;
     protocol.delegate_max_stake_usdc_minor = params.delegate_max_stake_usdc_minor;
-    protocol.paused = false;
+    protocol.reveal_timeout_seconds = params.reveal_timeout_seconds;
+    protocol.min_artist_bond_usdc_minor = params.min_artist_bond_usdc_minor;
+    protocol.artist_bond_slash_bps = params.artist_bond_slash_bps;
+    protocol.bond_withdrawal_cooldown_seconds = params.bond_withdrawal_cooldown_seconds;
+    protocol.cancellation_fee_bps = params.cancellation_fee_bps;
+    protocol.config_timelock_seconds = params.config_timelock_seconds;
+    protocol.treasury_destination = params.treasury_destination;
+    protocol.dust_destination = params.dust_destination;
+    protocol.pending_admin = Pubkey::default();
+    protocol.pause_flags = 0;
     protocol.bump = ctx.bumps.protocol;
     Ok(())
 }
 
-pub fn update_protocol_config(
-    ctx: Context<UpdateProtocolConfig>,
+pub fn propose_protocol_config(
+    ctx: Context<ProposeProtocolConfig>,
     params: UpdateProtocolConfigParams,
 ) -> Result<()> {
     validate_fee_split(
@@ -59,7 +106,54 @@ pub fn update_protocol_config(
         params.max_stake_usdc_minor >= params.min_stake_usdc_minor,
         ErrorCode::InvalidStakeRange
     );
+    require!(
+        params.reveal_timeout_seconds > 0,
+        ErrorCode::InvalidRevealTimeout
+    );
+    require!(
+        params.artist_bond_slash_bps <= 10_000 && params.bond_withdrawal_cooldown_seconds >= 0,
+        ErrorCode::InvalidArtistBondConfig
+    );
+    require!(
+        params.cancellation_fee_bps <= 10_000,
+        ErrorCode::InvalidCancellationFee
+    );
+
+    require!(
+        params.config_timelock_seconds >= 0,
+        ErrorCode::InvalidConfigTimelock
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let executable_ts = now
+        .checked_add(ctx.accounts.protocol.config_timelock_seconds)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let pending_config = &mut ctx.accounts.pending_config;
+    pending_config.protocol = ctx.accounts.protocol.key();
+    pending_config.params = params;
+    pending_config.proposed_ts = now;
+    pending_config.executable_ts = executable_ts;
+    pending_config.bump = ctx.bumps.pending_config;
+
+    emit!(ProtocolConfigProposed {
+        protocol: pending_config.protocol,
+        pending_config: pending_config.key(),
+        proposed_ts: now,
+        executable_ts,
+    });
+
+    Ok(())
+}
+
+pub fn execute_protocol_config(ctx: Context<ResolveProtocolConfig>) -> Result<()> {
+    let pending_config = &ctx.accounts.pending_config;
+    require!(
+        Clock::get()?.unix_timestamp >= pending_config.executable_ts,
+        ErrorCode::ConfigTimelockActive
+    );
 
+    let params = &pending_config.params;
     let protocol = &mut ctx.accounts.protocol;
     protocol.platform_fee_bps = params.platform_fee_bps;
     protocol.artist_pending_bps = params.artist_pending_bps;
@@ -68,13 +162,133 @@ pub fn update_protocol_config(
     protocol.min_stake_usdc_minor = params.min_stake_usdc_minor;
     protocol.max_stake_usdc_minor = params.max_stake_usdc_minor;
     protocol.min_launch_quote_usdc_minor = params.min_launch_quote_usdc_minor;
-    protocol.prediction_delegate = params.prediction_delegate;
-    protocol.delegate_max_stake_usdc_minor = params.delegate_max_stake_usdc_minor;
+    protocol.reveal_timeout_seconds = params.reveal_timeout_seconds;
+    protocol.min_artist_bond_usdc_minor = params.min_artist_bond_usdc_minor;
+    protocol.artist_bond_slash_bps = params.artist_bond_slash_bps;
+    protocol.bond_withdrawal_cooldown_seconds = params.bond_withdrawal_cooldown_seconds;
+    protocol.cancellation_fee_bps = params.cancellation_fee_bps;
+    protocol.config_timelock_seconds = params.config_timelock_seconds;
+    protocol.treasury_destination = params.treasury_destination;
+    protocol.dust_destination = params.dust_destination;
+
+    emit!(ProtocolConfigExecuted {
+        protocol: protocol.key(),
+        pending_config: pending_config.key(),
+    });
+
+    Ok(())
+}
+
+pub fn cancel_protocol_config(ctx: Context<ResolveProtocolConfig>) -> Result<()> {
+    emit!(ProtocolConfigCancelled {
+        protocol: ctx.accounts.protocol.key(),
+        pending_config: ctx.accounts.pending_config.key(),
+    });
+    Ok(())
+}
+
+pub fn set_protocol_roles(
+    ctx: Context<SetProtocolRoles>,
+    params: SetProtocolRolesParams,
+) -> Result<()> {
+    let protocol = &mut ctx.accounts.protocol;
+    protocol.pauser = params.pauser;
+    protocol.fee_manager = params.fee_manager;
+    protocol.treasury = params.treasury;
+    protocol.liquidity_deployer = params.liquidity_deployer;
+    protocol.delegate_manager = params.delegate_manager;
+
+    emit!(ProtocolRolesUpdated {
+        protocol: protocol.key(),
+        pauser: params.pauser,
+        fee_manager: params.fee_manager,
+        treasury: params.treasury,
+        liquidity_deployer: params.liquidity_deployer,
+        delegate_manager: params.delegate_manager,
+    });
+
+    Ok(())
+}
+
+pub fn set_prediction_delegate(
+    ctx: Context<SetPredictionDelegate>,
+    prediction_delegate: Pubkey,
+    delegate_max_stake_usdc_minor: u64,
+) -> Result<()> {
+    let protocol = &mut ctx.accounts.protocol;
+    protocol.prediction_delegate = prediction_delegate;
+    protocol.delegate_max_stake_usdc_minor = delegate_max_stake_usdc_minor;
+
+    emit!(PredictionDelegateUpdated {
+        protocol: protocol.key(),
+        prediction_delegate,
+        delegate_max_stake_usdc_minor,
+    });
+
+    Ok(())
+}
+
+/// Nominates the next admin; `Pubkey::default()` withdraws a nomination.
+/// The admin only changes once the nominee signs `accept_admin`.
+pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+    let protocol = &mut ctx.accounts.protocol;
+    protocol.pending_admin = new_admin;
+
+    emit!(AdminProposed {
+        protocol: protocol.key(),
+        admin: protocol.admin,
+        pending_admin: new_admin,
+    });
+
+    Ok(())
+}
+
+pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let protocol = &mut ctx.accounts.protocol;
+    require!(
+        protocol.pending_admin != Pubkey::default(),
+        ErrorCode::NotPendingAdmin
+    );
+
+    let previous_admin = protocol.admin;
+    protocol.admin = protocol.pending_admin;
+    protocol.pending_admin = Pubkey::default();
+
+    emit!(AdminTransferred {
+        protocol: protocol.key(),
+        previous_admin,
+        new_admin: protocol.admin,
+    });
+
+    Ok(())
+}
+
+pub fn set_protocol_pause_flags(
+    ctx: Context<SetProtocolPauseFlags>,
+    pause_flags: u8,
+) -> Result<()> {
+    validate_pause_flags(pause_flags)?;
+    let protocol = &mut ctx.accounts.protocol;
+    protocol.pause_flags = pause_flags;
+
+    emit!(ProtocolPauseFlagsUpdated {
+        protocol: protocol.key(),
+        pause_flags,
+    });
+
     Ok(())
 }
 
-pub fn set_protocol_paused(ctx: Context<SetProtocolPaused>, paused: bool) -> Result<()> {
-    ctx.accounts.protocol.paused = paused;
+pub fn set_room_pause_flags(ctx: Context<SetRoomPauseFlags>, pause_flags: u8) -> Result<()> {
+    validate_pause_flags(pause_flags)?;
+    let room = &mut ctx.accounts.room;
+    room.pause_flags = pause_flags;
+
+    emit!(RoomPauseFlagsUpdated {
+        room: room.key(),
+        pause_flags,
+    });
+
     Ok(())
 }
 
@@ -93,6 +307,7 @@ pub fn create_room(ctx: Context<CreateRoom>, params: CreateRoomParams) -> Result
         ),
         ErrorCode::RewardMintFreezeAuthorityMismatch
     );
+    validate_market_config(params.market_mode, params.lmsr_liquidity_usdc_minor)?;
 
     let room = &mut ctx.accounts.room;
     room.protocol = ctx.accounts.protocol.key();
@@ -103,28 +318,269 @@ pub fn create_room(ctx: Context<CreateRoom>, params: CreateRoomParams) -> Result
     room.next_round_index = 0;
     room.pending_winner_rollover_usdc_minor = 0;
     room.pending_liquidity_rollover_usdc_minor = 0;
+    room.artist_bond_usdc_minor = 0;
+    room.bond_withdrawal_pending_usdc_minor = 0;
+    room.bond_withdrawal_requested_ts = 0;
+    room.bonded_rounds = 0;
+    room.operators = [Pubkey::default(); MAX_ROOM_OPERATORS];
+    room.operator_count = 0;
+    room.market_mode = params.market_mode;
+    room.lmsr_liquidity_usdc_minor = params.lmsr_liquidity_usdc_minor;
+    room.pause_flags = 0;
+    room.has_fee_override = false;
+    room.platform_fee_accrued_usdc_minor = 0;
+    room.artist_earned_usdc_minor = 0;
+    room.artist_claimed_usdc_minor = 0;
+    room.artist_pending_liability_usdc_minor = 0;
+    room.platform_fee_liability_usdc_minor = 0;
+    room.liquidity_reserve_liability_usdc_minor = 0;
+    room.winner_pot_liability_usdc_minor = 0;
     room.bump = ctx.bumps.room;
     Ok(())
 }
 
+pub fn set_room_fee_override(
+    ctx: Context<SetRoomFeeOverride>,
+    params: SetRoomFeeOverrideParams,
+) -> Result<()> {
+    validate_fee_split(
+        params.platform_fee_bps,
+        params.artist_pending_bps,
+        params.liquidity_reserve_bps,
+        params.winner_pot_bps,
+    )?;
+
+    let room = &mut ctx.accounts.room;
+    let room_fee_override = &mut ctx.accounts.room_fee_override;
+    room_fee_override.room = room.key();
+    room_fee_override.platform_fee_bps = params.platform_fee_bps;
+    room_fee_override.artist_pending_bps = params.artist_pending_bps;
+    room_fee_override.liquidity_reserve_bps = params.liquidity_reserve_bps;
+    room_fee_override.winner_pot_bps = params.winner_pot_bps;
+    room_fee_override.bump = ctx.bumps.room_fee_override;
+    room.has_fee_override = true;
+
+    emit!(RoomFeeOverrideSet {
+        room: room.key(),
+        platform_fee_bps: params.platform_fee_bps,
+        artist_pending_bps: params.artist_pending_bps,
+        liquidity_reserve_bps: params.liquidity_reserve_bps,
+        winner_pot_bps: params.winner_pot_bps,
+    });
+
+    Ok(())
+}
+
+pub fn remove_room_fee_override(ctx: Context<RemoveRoomFeeOverride>) -> Result<()> {
+    let room = &mut ctx.accounts.room;
+    room.has_fee_override = false;
+
+    emit!(RoomFeeOverrideRemoved { room: room.key() });
+    Ok(())
+}
+
+pub fn set_room_market_mode(
+    ctx: Context<SetRoomMarketMode>,
+    params: SetRoomMarketModeParams,
+) -> Result<()> {
+    validate_market_config(params.market_mode, params.lmsr_liquidity_usdc_minor)?;
+
+    // Rounds snapshot the mode at start, so this only affects later rounds.
+    let room = &mut ctx.accounts.room;
+    room.market_mode = params.market_mode;
+    room.lmsr_liquidity_usdc_minor = params.lmsr_liquidity_usdc_minor;
+    Ok(())
+}
+
+pub fn add_room_operator(ctx: Context<ManageRoomOperators>, operator: Pubkey) -> Result<()> {
+    let room = &mut ctx.accounts.room;
+    let count = room.operator_count as usize;
+    require!(
+        !is_room_excluded_wallet(room.artist, &room.operators[..count], operator),
+        ErrorCode::RoomOperatorAlreadyRegistered
+    );
+    require!(count < MAX_ROOM_OPERATORS, ErrorCode::RoomOperatorListFull);
+
+    room.operators[count] = operator;
+    room.operator_count = room
+        .operator_count
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(())
+}
+
+pub fn remove_room_operator(ctx: Context<ManageRoomOperators>, operator: Pubkey) -> Result<()> {
+    let room = &mut ctx.accounts.room;
+    let count = room.operator_count as usize;
+    let index = room.operators[..count]
+        .iter()
+        .position(|key| *key == operator)
+        .ok_or(ErrorCode::RoomOperatorNotFound)?;
+
+    room.operators[index] = room.operators[count - 1];
+    room.operators[count - 1] = Pubkey::default();
+    room.operator_count -= 1;
+    Ok(())
+}
+
+pub fn deposit_artist_bond(ctx: Context<DepositArtistBond>, amount_usdc_minor: u64) -> Result<()> {
+    transfer_quote_from_user(
+        &ctx.accounts.artist,
+        &ctx.accounts.artist_quote_ata,
+        &ctx.accounts.artist_bond_vault,
+        &ctx.accounts.quote_mint,
+        &ctx.accounts.token_program,
+        amount_usdc_minor,
+    )?;
+
+    let room = &mut ctx.accounts.room;
+    room.artist_bond_usdc_minor = room
+        .artist_bond_usdc_minor
+        .checked_add(amount_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    emit!(ArtistBondDeposited {
+        room: room.key(),
+        amount_usdc_minor,
+        bond_usdc_minor: room.artist_bond_usdc_minor,
+    });
+
+    Ok(())
+}
+
+pub fn request_artist_bond_withdrawal(
+    ctx: Context<RequestArtistBondWithdrawal>,
+    amount_usdc_minor: u64,
+) -> Result<()> {
+    let room = &mut ctx.accounts.room;
+    require!(
+        amount_usdc_minor > 0 && amount_usdc_minor <= room.artist_bond_usdc_minor,
+        ErrorCode::InvalidBondWithdrawalAmount
+    );
+
+    room.bond_withdrawal_pending_usdc_minor = amount_usdc_minor;
+    room.bond_withdrawal_requested_ts = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+pub fn withdraw_artist_bond(ctx: Context<WithdrawArtistBond>) -> Result<()> {
+    require_not_paused(&ctx.accounts.protocol, &ctx.accounts.room, PAUSE_CLAIMS)?;
+    let protocol = &ctx.accounts.protocol;
+    let room = &mut ctx.accounts.room;
+
+    require!(
+        room.bond_withdrawal_pending_usdc_minor > 0,
+        ErrorCode::NoBondWithdrawalRequested
+    );
+    require!(room.bonded_rounds == 0, ErrorCode::ArtistBondLocked);
+    let unlock_ts = room
+        .bond_withdrawal_requested_ts
+        .checked_add(protocol.bond_withdrawal_cooldown_seconds)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(
+        Clock::get()?.unix_timestamp >= unlock_ts,
+        ErrorCode::BondWithdrawalCooldownActive
+    );
+
+    // A slash during the cooldown can leave less bond than was requested.
+    let amount = room
+        .bond_withdrawal_pending_usdc_minor
+        .min(room.artist_bond_usdc_minor);
+    transfer_quote_from_vault(
+        room,
+        ctx.bumps.vault_authority,
+        &ctx.accounts.vault_authority,
+        &ctx.accounts.artist_bond_vault,
+        &ctx.accounts.artist_quote_ata,
+        &ctx.accounts.quote_mint,
+        &ctx.accounts.token_program,
+        amount,
+    )?;
+
+    room.artist_bond_usdc_minor = room
+        .artist_bond_usdc_minor
+        .checked_sub(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    room.bond_withdrawal_pending_usdc_minor = 0;
+    room.bond_withdrawal_requested_ts = 0;
+
+    emit!(ArtistBondWithdrawn {
+        room: room.key(),
+        amount_usdc_minor: amount,
+        bond_usdc_minor: room.artist_bond_usdc_minor,
+    });
+
+    Ok(())
+}
+
 pub fn start_round(ctx: Context<StartRound>, params: StartRoundParams) -> Result<()> {
+    require_not_paused(
+        &ctx.accounts.protocol,
+        &ctx.accounts.room,
+        PAUSE_PREDICTIONS,
+    )?;
+    let protocol = &ctx.accounts.protocol;
     let room = &mut ctx.accounts.room;
     let round = &mut ctx.accounts.round;
 
     round.room = room.key();
     round.index = room.next_round_index;
     round.phase = RoundPhase::AwaitingCommit;
+    round.market_mode = room.market_mode;
     round.bpm = params.bpm;
+    round.commit_version = 0;
     round.commit_hash = [0u8; 32];
     round.total_predictions = 0;
+    round.cancelled_positions = 0;
+    round.open_positions = 0;
+    round.shard_count = 0;
+    match (room.has_fee_override, &ctx.accounts.room_fee_override) {
+        (true, Some(room_fee_override)) => {
+            round.platform_fee_bps = room_fee_override.platform_fee_bps;
+            round.artist_pending_bps = room_fee_override.artist_pending_bps;
+            round.liquidity_reserve_bps = room_fee_override.liquidity_reserve_bps;
+            round.winner_pot_bps = room_fee_override.winner_pot_bps;
+        }
+        (false, None) => {
+            round.platform_fee_bps = protocol.platform_fee_bps;
+            round.artist_pending_bps = protocol.artist_pending_bps;
+            round.liquidity_reserve_bps = protocol.liquidity_reserve_bps;
+            round.winner_pot_bps = protocol.winner_pot_bps;
+        }
+        _ => return err!(ErrorCode::FeeOverrideMismatch),
+    }
+    round.dust_destination = protocol.dust_destination;
+    round.min_stake_usdc_minor = protocol.min_stake_usdc_minor;
+    round.max_stake_usdc_minor = protocol.max_stake_usdc_minor;
+    round.min_launch_quote_usdc_minor = protocol.min_launch_quote_usdc_minor;
+    round.cancellation_fee_bps = protocol.cancellation_fee_bps;
+    round.reveal_timeout_seconds = protocol.reveal_timeout_seconds;
+    round.artist_bond_slash_bps = protocol.artist_bond_slash_bps;
     round.total_staked_usdc_minor = 0;
+    round.dust_usdc_minor = 0;
     round.artist_pending_usdc_minor = 0;
     round.platform_fee_usdc_minor = 0;
     round.liquidity_reserve_usdc_minor = room.pending_liquidity_rollover_usdc_minor;
     round.winner_pot_usdc_minor = room.pending_winner_rollover_usdc_minor;
     round.winner_pot_distributed_usdc_minor = 0;
+    round.shared_winner_pot_usdc_minor = room.pending_winner_rollover_usdc_minor;
+    round.shared_winner_pot_distributed_usdc_minor = 0;
+    round.tile_yes_pool_usdc_minor = [0u64; TILE_COUNT];
+    round.tile_no_pool_usdc_minor = [0u64; TILE_COUNT];
+    round.lmsr_liquidity_usdc_minor = room.lmsr_liquidity_usdc_minor;
+    round.lmsr_subsidy_usdc_minor = 0;
+    round.carried_winner_pot_usdc_minor = room.pending_winner_rollover_usdc_minor;
+    round.carried_liquidity_usdc_minor = room.pending_liquidity_rollover_usdc_minor;
+    set_round_cancellation_fees(round, &StakeSplit::default());
+    round.bond_slash_usdc_minor = 0;
+    round.bond_slash_distributed_usdc_minor = 0;
+    round.prediction_close_ts = 0;
+    round.reveal_deadline_ts = 0;
     round.settled_positions = 0;
     round.winning_positions = 0;
+    round.tallied_positions = 0;
+    round.correct_positions = 0;
+    round.correct_stake_usdc_minor = 0;
     round.delegated_spent_usdc_minor = 0;
     round.outcome_bitmap = [0u8; REVEAL_BITMAP_BYTES];
     round.reveal_verified = false;
@@ -140,342 +596,1484 @@ pub fn start_round(ctx: Context<StartRound>, params: StartRoundParams) -> Result
     Ok(())
 }
 
-pub fn commit_round(ctx: Context<CommitRound>, params: CommitRoundParams) -> Result<()> {
+pub fn initialize_round_shard(ctx: Context<InitializeRoundShard>) -> Result<()> {
+    require_not_paused(
+        &ctx.accounts.protocol,
+        &ctx.accounts.room,
+        PAUSE_PREDICTIONS,
+    )?;
     let round = &mut ctx.accounts.round;
     require!(
         round.phase == RoundPhase::AwaitingCommit,
         ErrorCode::InvalidRoundPhase
     );
+    require!(
+        round.shard_count < MAX_ROUND_SHARDS,
+        ErrorCode::MaxRoundShardsReached
+    );
 
-    round.commit_hash = params.commit_hash;
-    round.phase = RoundPhase::PredictionOpen;
+    let round_shard = &mut ctx.accounts.round_shard;
+    round_shard.round = round.key();
+    round_shard.index = round.shard_count;
+    round_shard.bump = ctx.bumps.round_shard;
+    round_shard.escrow_bump = ctx.bumps.shard_escrow;
+    round.shard_count += 1;
     Ok(())
 }
 
-pub fn place_prediction(
-    ctx: Context<PlacePrediction>,
-    params: PlacePredictionParams,
-) -> Result<()> {
+pub fn commit_round(ctx: Context<CommitRound>, params: CommitRoundParams) -> Result<()> {
+    require_not_paused(
+        &ctx.accounts.protocol,
+        &ctx.accounts.room,
+        PAUSE_PREDICTIONS,
+    )?;
     let protocol = &ctx.accounts.protocol;
+    let room = &mut ctx.accounts.room;
     let round = &mut ctx.accounts.round;
-    let position = &mut ctx.accounts.position;
-
     require!(
-        round.phase == RoundPhase::PredictionOpen,
+        round.phase == RoundPhase::AwaitingCommit,
         ErrorCode::InvalidRoundPhase
     );
-    require!(!protocol.paused, ErrorCode::ProtocolPaused);
     require!(
-        params.stake_amount_usdc_minor >= protocol.min_stake_usdc_minor
-            && params.stake_amount_usdc_minor <= protocol.max_stake_usdc_minor,
-        ErrorCode::InvalidStakeAmount
+        params.prediction_close_ts > Clock::get()?.unix_timestamp,
+        ErrorCode::InvalidPredictionCloseTime
+    );
+    validate_commit_version(params.commit_version)?;
+    // Only parimutuel placements go through shards; LMSR purchases write
+    // `Round` directly.
+    require!(
+        round.market_mode != MarketMode::Parimutuel || round.shard_count > 0,
+        ErrorCode::RoundShardsMissing
+    );
+    let unencumbered_bond = room
+        .artist_bond_usdc_minor
+        .saturating_sub(room.bond_withdrawal_pending_usdc_minor);
+    require!(
+        unencumbered_bond >= protocol.min_artist_bond_usdc_minor,
+        ErrorCode::InsufficientArtistBond
     );
-    validate_prediction_indices(params.track_index, params.step_index)?;
-
-    let artist_pending = split_amount(params.stake_amount_usdc_minor, protocol.artist_pending_bps)?;
-    let platform_fee = split_amount(params.stake_amount_usdc_minor, protocol.platform_fee_bps)?;
-    let liquidity = split_amount(
-        params.stake_amount_usdc_minor,
-        protocol.liquidity_reserve_bps,
-    )?;
-    let winner = params
-        .stake_amount_usdc_minor
-        .checked_sub(artist_pending)
-        .and_then(|v| v.checked_sub(platform_fee))
-        .and_then(|v| v.checked_sub(liquidity))
-        .ok_or(ErrorCode::MathOverflow)?;
 
-    round.total_predictions = round
-        .total_predictions
+    room.bonded_rounds = room
+        .bonded_rounds
         .checked_add(1)
         .ok_or(ErrorCode::MathOverflow)?;
-    round.total_staked_usdc_minor = round
-        .total_staked_usdc_minor
-        .checked_add(params.stake_amount_usdc_minor)
-        .ok_or(ErrorCode::MathOverflow)?;
-    round.artist_pending_usdc_minor = round
-        .artist_pending_usdc_minor
-        .checked_add(artist_pending)
-        .ok_or(ErrorCode::MathOverflow)?;
-    round.platform_fee_usdc_minor = round
-        .platform_fee_usdc_minor
-        .checked_add(platform_fee)
-        .ok_or(ErrorCode::MathOverflow)?;
-    round.liquidity_reserve_usdc_minor = round
-        .liquidity_reserve_usdc_minor
-        .checked_add(liquidity)
-        .ok_or(ErrorCode::MathOverflow)?;
-    round.winner_pot_usdc_minor = round
-        .winner_pot_usdc_minor
-        .checked_add(winner)
-        .ok_or(ErrorCode::MathOverflow)?;
 
-    position.round = round.key();
-    position.user = ctx.accounts.user.key();
-    position.track_index = params.track_index;
-    position.step_index = params.step_index;
-    position.will_be_active = params.will_be_active;
-    position.stake_amount_usdc_minor = params.stake_amount_usdc_minor;
-    position.was_correct = false;
-    position.usdc_payout_usdc_minor = 0;
-    position.settled = false;
-    position.claimed = false;
-    position.bump = ctx.bumps.position;
+    if round.market_mode == MarketMode::Lmsr {
+        let subsidy = lmsr_tile_subsidy(round.lmsr_liquidity_usdc_minor)?
+            .checked_mul(TILE_COUNT as u64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            round.liquidity_reserve_usdc_minor >= subsidy,
+            ErrorCode::InsufficientLmsrLiquidity
+        );
+        debit_room_escrow_ledger(
+            room,
+            &StakeSplit {
+                liquidity_reserve_usdc_minor: subsidy,
+                ..StakeSplit::default()
+            },
+        )?;
+        credit_room_escrow_ledger(
+            room,
+            &StakeSplit {
+                winner_pot_usdc_minor: subsidy,
+                ..StakeSplit::default()
+            },
+        )?;
+        round.liquidity_reserve_usdc_minor -= subsidy;
+        round.winner_pot_usdc_minor = round
+            .winner_pot_usdc_minor
+            .checked_add(subsidy)
+            .ok_or(ErrorCode::MathOverflow)?;
+        round.lmsr_subsidy_usdc_minor = subsidy;
+    }
 
-    transfer_quote_from_user(
+    round.commit_version = params.commit_version;
+    round.commit_hash = params.commit_hash;
+    round.prediction_close_ts = params.prediction_close_ts;
+    round.phase = RoundPhase::PredictionOpen;
+    Ok(())
+}
+
+pub fn place_prediction(
+    ctx: Context<PlacePrediction>,
+    params: PlacePredictionParams,
+) -> Result<()> {
+    let protocol = &ctx.accounts.protocol;
+    let room = &ctx.accounts.room;
+    let round = &ctx.accounts.round;
+    let round_shard = &mut ctx.accounts.round_shard;
+    let user = ctx.accounts.user.key();
+
+    require_new_position(&ctx.accounts.position)?;
+    validate_prediction(protocol, room, round, user, false, MarketMode::Parimutuel)?;
+    require_user_round_shard(round, round_shard, user)?;
+    let (entry, split, dust) = build_prediction_entry(round, &params.entry())?;
+    record_shard_prediction(
+        round_shard,
+        &mut ctx.accounts.position,
+        user,
+        params.client_nonce,
+        vec![entry],
+        &split,
+        dust,
+        ctx.bumps.position,
+    )?;
+
+    transfer_stake_to_escrow(
         &ctx.accounts.user,
         &ctx.accounts.user_quote_ata,
-        &ctx.accounts.artist_pending_vault,
+        &ctx.accounts.shard_escrow,
         &ctx.accounts.quote_mint,
         &ctx.accounts.token_program,
-        artist_pending,
+        &split,
     )?;
-    transfer_quote_from_user(
-        &ctx.accounts.user,
+
+    emit!(PredictionPlaced {
+        room: room.key(),
+        round: round.key(),
+        user,
+        stake_amount_usdc_minor: params.stake_amount_usdc_minor,
+        entry_count: 1,
+        delegated: false,
+        client_nonce: params.client_nonce,
+        platform_fee_bps: round.platform_fee_bps,
+        artist_pending_bps: round.artist_pending_bps,
+        liquidity_reserve_bps: round.liquidity_reserve_bps,
+        winner_pot_bps: round.winner_pot_bps,
+    });
+
+    Ok(())
+}
+
+pub fn place_prediction_delegated(
+    ctx: Context<PlacePredictionDelegated>,
+    params: PlacePredictionParams,
+) -> Result<()> {
+    let protocol = &ctx.accounts.protocol;
+    let room = &ctx.accounts.room;
+    let round = &mut ctx.accounts.round;
+    let round_shard = &mut ctx.accounts.round_shard;
+    let user = ctx.accounts.user.key();
+
+    require_new_position(&ctx.accounts.position)?;
+    validate_prediction(protocol, room, round, user, true, MarketMode::Parimutuel)?;
+    require_user_round_shard(round, round_shard, user)?;
+    let (entry, split, dust) = build_prediction_entry(round, &params.entry())?;
+    validate_delegated_prediction_signer(
+        protocol.prediction_delegate,
+        protocol.delegate_max_stake_usdc_minor,
+        ctx.accounts.session_delegate.key(),
+        params.stake_amount_usdc_minor,
+    )?;
+
+    let delegated_spent_next = delegated_round_spend(
+        round.delegated_spent_usdc_minor,
+        params.stake_amount_usdc_minor,
+        protocol.delegate_max_stake_usdc_minor,
+    )?;
+
+    record_shard_prediction(
+        round_shard,
+        &mut ctx.accounts.position,
+        user,
+        params.client_nonce,
+        vec![entry],
+        &split,
+        dust,
+        ctx.bumps.position,
+    )?;
+    round.delegated_spent_usdc_minor = delegated_spent_next;
+
+    transfer_stake_to_escrow(
+        &ctx.accounts.session_delegate,
         &ctx.accounts.user_quote_ata,
-        &ctx.accounts.platform_fee_vault,
+        &ctx.accounts.shard_escrow,
         &ctx.accounts.quote_mint,
         &ctx.accounts.token_program,
-        platform_fee,
+        &split,
     )?;
-    transfer_quote_from_user(
+
+    emit!(PredictionPlaced {
+        room: room.key(),
+        round: round.key(),
+        user,
+        stake_amount_usdc_minor: params.stake_amount_usdc_minor,
+        entry_count: 1,
+        delegated: true,
+        client_nonce: params.client_nonce,
+        platform_fee_bps: round.platform_fee_bps,
+        artist_pending_bps: round.artist_pending_bps,
+        liquidity_reserve_bps: round.liquidity_reserve_bps,
+        winner_pot_bps: round.winner_pot_bps,
+    });
+
+    Ok(())
+}
+
+pub fn place_prediction_batch(
+    ctx: Context<PlacePredictionBatch>,
+    params: PlacePredictionBatchParams,
+) -> Result<()> {
+    let protocol = &ctx.accounts.protocol;
+    let room = &ctx.accounts.room;
+    let round = &ctx.accounts.round;
+    let round_shard = &mut ctx.accounts.round_shard;
+    let user = ctx.accounts.user.key();
+
+    require_new_position(&ctx.accounts.position)?;
+    validate_batch_size(params.entries.len())?;
+    validate_prediction(protocol, room, round, user, false, MarketMode::Parimutuel)?;
+    require_user_round_shard(round, round_shard, user)?;
+
+    // Each entry is split on its own stake so its winner-pot leg is known for
+    // the tile pool; the legs are then summed and escrowed in one transfer.
+    let mut entries = Vec::with_capacity(params.entries.len());
+    let mut split = StakeSplit::default();
+    let mut dust = 0u64;
+    for entry_params in &params.entries {
+        let (entry, entry_split, entry_dust) = build_prediction_entry(round, entry_params)?;
+        split = add_stake_splits(&split, &entry_split)?;
+        dust = dust
+            .checked_add(entry_dust)
+            .ok_or(ErrorCode::MathOverflow)?;
+        entries.push(entry);
+    }
+    let entry_count = entries.len() as u8;
+    record_shard_prediction(
+        round_shard,
+        &mut ctx.accounts.position,
+        user,
+        params.client_nonce,
+        entries,
+        &split,
+        dust,
+        ctx.bumps.position,
+    )?;
+
+    transfer_stake_to_escrow(
         &ctx.accounts.user,
         &ctx.accounts.user_quote_ata,
-        &ctx.accounts.liquidity_reserve_vault,
+        &ctx.accounts.shard_escrow,
         &ctx.accounts.quote_mint,
         &ctx.accounts.token_program,
-        liquidity,
+        &split,
     )?;
-    transfer_quote_from_user(
+
+    emit!(PredictionPlaced {
+        room: room.key(),
+        round: round.key(),
+        user,
+        stake_amount_usdc_minor: ctx.accounts.position.stake_amount_usdc_minor,
+        entry_count,
+        delegated: false,
+        client_nonce: params.client_nonce,
+        platform_fee_bps: round.platform_fee_bps,
+        artist_pending_bps: round.artist_pending_bps,
+        liquidity_reserve_bps: round.liquidity_reserve_bps,
+        winner_pot_bps: round.winner_pot_bps,
+    });
+
+    Ok(())
+}
+
+pub fn buy_tile_shares(ctx: Context<BuyTileShares>, params: BuyTileSharesParams) -> Result<()> {
+    let protocol = &ctx.accounts.protocol;
+    let room = &mut ctx.accounts.room;
+    let round = &mut ctx.accounts.round;
+    let user = ctx.accounts.user.key();
+
+    require_new_position(&ctx.accounts.position)?;
+    validate_prediction(protocol, room, round, user, false, MarketMode::Lmsr)?;
+
+    // Fee legs are split as usual; the winner-pot leg is what buys shares.
+    let (mut entry, split, dust) = build_prediction_entry(
+        round,
+        &PredictionEntryParams {
+            track_index: params.track_index,
+            step_index: params.step_index,
+            will_be_active: params.will_be_active,
+            stake_amount_usdc_minor: params.stake_amount_usdc_minor,
+        },
+    )?;
+    let tile = tile_index(params.track_index, params.step_index)?;
+    let (q_side, q_other) = if params.will_be_active {
+        (
+            round.tile_yes_pool_usdc_minor[tile],
+            round.tile_no_pool_usdc_minor[tile],
+        )
+    } else {
+        (
+            round.tile_no_pool_usdc_minor[tile],
+            round.tile_yes_pool_usdc_minor[tile],
+        )
+    };
+    let shares = lmsr_shares_for_cost(
+        q_side,
+        q_other,
+        round.lmsr_liquidity_usdc_minor,
+        split.winner_pot_usdc_minor,
+    )?;
+    require!(
+        shares > 0 && shares >= params.min_shares,
+        ErrorCode::SharesBelowMinimum
+    );
+    entry.shares = shares;
+
+    record_prediction(
+        round,
+        &mut ctx.accounts.position,
+        user,
+        params.client_nonce,
+        vec![entry],
+        &split,
+        dust,
+        ctx.bumps.position,
+    )?;
+
+    transfer_stake_to_escrow(
         &ctx.accounts.user,
         &ctx.accounts.user_quote_ata,
-        &ctx.accounts.winner_pot_vault,
+        &ctx.accounts.room_escrow,
         &ctx.accounts.quote_mint,
         &ctx.accounts.token_program,
-        winner,
+        &split,
     )?;
+    credit_room_escrow_ledger(room, &split)?;
 
     emit!(PredictionPlaced {
-        room: ctx.accounts.room.key(),
+        room: room.key(),
         round: round.key(),
-        user: ctx.accounts.user.key(),
+        user,
         stake_amount_usdc_minor: params.stake_amount_usdc_minor,
+        entry_count: 1,
         delegated: false,
+        client_nonce: params.client_nonce,
+        platform_fee_bps: round.platform_fee_bps,
+        artist_pending_bps: round.artist_pending_bps,
+        liquidity_reserve_bps: round.liquidity_reserve_bps,
+        winner_pot_bps: round.winner_pot_bps,
+    });
+    emit!(TileSharesBought {
+        room: room.key(),
+        round: round.key(),
+        user,
+        track_index: params.track_index,
+        step_index: params.step_index,
+        will_be_active: params.will_be_active,
+        cost_usdc_minor: split.winner_pot_usdc_minor,
+        shares,
     });
 
     Ok(())
 }
 
-pub fn place_prediction_delegated(
-    ctx: Context<PlacePredictionDelegated>,
-    params: PlacePredictionParams,
+fn validate_prediction(
+    protocol: &ProtocolConfig,
+    room: &Account<Room>,
+    round: &Account<Round>,
+    user: Pubkey,
+    delegated: bool,
+    market_mode: MarketMode,
 ) -> Result<()> {
-    let protocol = &ctx.accounts.protocol;
-    let round = &mut ctx.accounts.round;
-    let position = &mut ctx.accounts.position;
+    if is_room_excluded_wallet(
+        room.artist,
+        &room.operators[..room.operator_count as usize],
+        user,
+    ) {
+        emit!(PredictionBlocked {
+            room: room.key(),
+            round: round.key(),
+            user,
+            delegated,
+        });
+        return err!(ErrorCode::ExcludedPredictor);
+    }
 
+    require!(
+        round.market_mode == market_mode,
+        ErrorCode::InvalidMarketMode
+    );
     require!(
         round.phase == RoundPhase::PredictionOpen,
         ErrorCode::InvalidRoundPhase
     );
-    require!(!protocol.paused, ErrorCode::ProtocolPaused);
     require!(
-        params.stake_amount_usdc_minor >= protocol.min_stake_usdc_minor
-            && params.stake_amount_usdc_minor <= protocol.max_stake_usdc_minor,
+        Clock::get()?.unix_timestamp < round.prediction_close_ts,
+        ErrorCode::PredictionWindowClosed
+    );
+    require_not_paused(protocol, room, PAUSE_PREDICTIONS)
+}
+
+/// Validates one tile prediction against the round's snapshot and splits
+/// its stake. Stake bounds apply per entry. Also returns the split's
+/// rounding dust.
+fn build_prediction_entry(
+    round: &Round,
+    params: &PredictionEntryParams,
+) -> Result<(PositionEntry, StakeSplit, u64)> {
+    require!(
+        params.stake_amount_usdc_minor >= round.min_stake_usdc_minor
+            && params.stake_amount_usdc_minor <= round.max_stake_usdc_minor,
         ErrorCode::InvalidStakeAmount
     );
     validate_prediction_indices(params.track_index, params.step_index)?;
-    validate_delegated_prediction_signer(
-        protocol.prediction_delegate,
-        protocol.delegate_max_stake_usdc_minor,
-        ctx.accounts.session_delegate.key(),
+
+    let (split, dust) = split_stake(
         params.stake_amount_usdc_minor,
+        round.artist_pending_bps,
+        round.platform_fee_bps,
+        round.liquidity_reserve_bps,
+        round.winner_pot_bps,
+        round.dust_destination,
     )?;
+    let entry = PositionEntry {
+        track_index: params.track_index,
+        step_index: params.step_index,
+        will_be_active: params.will_be_active,
+        stake_amount_usdc_minor: params.stake_amount_usdc_minor,
+        winner_pot_usdc_minor: split.winner_pot_usdc_minor,
+        shares: 0,
+        was_correct: false,
+    };
+    Ok((entry, split, dust))
+}
 
-    let delegated_spent_next = round
-        .delegated_spent_usdc_minor
-        .checked_add(params.stake_amount_usdc_minor)
-        .ok_or(ErrorCode::MathOverflow)?;
+/// Fails unless `round_shard` is the shard `user`'s placements map to.
+fn require_user_round_shard(round: &Round, round_shard: &RoundShard, user: Pubkey) -> Result<()> {
     require!(
-        delegated_spent_next <= protocol.delegate_max_stake_usdc_minor,
-        ErrorCode::DelegatedRoundCapExceeded
+        round_shard_index(&user, round.shard_count)? == round_shard.index,
+        ErrorCode::WrongRoundShard
     );
+    Ok(())
+}
 
-    let artist_pending = split_amount(params.stake_amount_usdc_minor, protocol.artist_pending_bps)?;
-    let platform_fee = split_amount(params.stake_amount_usdc_minor, protocol.platform_fee_bps)?;
-    let liquidity = split_amount(
-        params.stake_amount_usdc_minor,
-        protocol.liquidity_reserve_bps,
+/// Adds entries to a pair of tile pools and returns their summed stake.
+/// Parimutuel entries add their winner-pot leg to their tile; LMSR entries
+/// add their shares instead.
+fn add_entries_to_tile_pools(
+    market_mode: MarketMode,
+    tile_yes_pool: &mut [u64; TILE_COUNT],
+    tile_no_pool: &mut [u64; TILE_COUNT],
+    entries: &[PositionEntry],
+) -> Result<u64> {
+    let mut stake_amount = 0u64;
+    for entry in entries {
+        stake_amount = stake_amount
+            .checked_add(entry.stake_amount_usdc_minor)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let tile_increment = match market_mode {
+            MarketMode::Parimutuel => entry.winner_pot_usdc_minor,
+            MarketMode::Lmsr => entry.shares,
+        };
+        let tile = tile_index(entry.track_index, entry.step_index)?;
+        let tile_pool = if entry.will_be_active {
+            &mut tile_yes_pool[tile]
+        } else {
+            &mut tile_no_pool[tile]
+        };
+        *tile_pool = tile_pool
+            .checked_add(tile_increment)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+    Ok(stake_amount)
+}
+
+/// Records an LMSR position straight onto the round, since its share price
+/// depends on every earlier purchase. `split` and `dust_usdc_minor` are the
+/// sums over the entries.
+#[allow(clippy::too_many_arguments)]
+fn record_prediction(
+    round: &mut Account<Round>,
+    position: &mut PredictionPosition,
+    user: Pubkey,
+    client_nonce: u64,
+    entries: Vec<PositionEntry>,
+    split: &StakeSplit,
+    dust_usdc_minor: u64,
+    bump: u8,
+) -> Result<()> {
+    let round_key = round.key();
+    let round: &mut Round = round;
+    let stake_amount = add_entries_to_tile_pools(
+        round.market_mode,
+        &mut round.tile_yes_pool_usdc_minor,
+        &mut round.tile_no_pool_usdc_minor,
+        &entries,
     )?;
-    let winner = params
-        .stake_amount_usdc_minor
-        .checked_sub(artist_pending)
-        .and_then(|v| v.checked_sub(platform_fee))
-        .and_then(|v| v.checked_sub(liquidity))
-        .ok_or(ErrorCode::MathOverflow)?;
 
     round.total_predictions = round
         .total_predictions
         .checked_add(1)
         .ok_or(ErrorCode::MathOverflow)?;
+    round.open_positions = round
+        .open_positions
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
     round.total_staked_usdc_minor = round
         .total_staked_usdc_minor
-        .checked_add(params.stake_amount_usdc_minor)
+        .checked_add(stake_amount)
         .ok_or(ErrorCode::MathOverflow)?;
     round.artist_pending_usdc_minor = round
         .artist_pending_usdc_minor
-        .checked_add(artist_pending)
+        .checked_add(split.artist_pending_usdc_minor)
         .ok_or(ErrorCode::MathOverflow)?;
     round.platform_fee_usdc_minor = round
         .platform_fee_usdc_minor
-        .checked_add(platform_fee)
+        .checked_add(split.platform_fee_usdc_minor)
         .ok_or(ErrorCode::MathOverflow)?;
     round.liquidity_reserve_usdc_minor = round
         .liquidity_reserve_usdc_minor
-        .checked_add(liquidity)
+        .checked_add(split.liquidity_reserve_usdc_minor)
         .ok_or(ErrorCode::MathOverflow)?;
     round.winner_pot_usdc_minor = round
         .winner_pot_usdc_minor
-        .checked_add(winner)
+        .checked_add(split.winner_pot_usdc_minor)
         .ok_or(ErrorCode::MathOverflow)?;
-    round.delegated_spent_usdc_minor = delegated_spent_next;
+    round.dust_usdc_minor = round
+        .dust_usdc_minor
+        .checked_add(dust_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    init_position(
+        position,
+        round_key,
+        user,
+        client_nonce,
+        entries,
+        stake_amount,
+        split,
+        bump,
+    );
+    Ok(())
+}
+
+/// Records a parimutuel position on its shard only; the totals reach the
+/// round when the shard is folded.
+#[allow(clippy::too_many_arguments)]
+fn record_shard_prediction(
+    round_shard: &mut RoundShard,
+    position: &mut PredictionPosition,
+    user: Pubkey,
+    client_nonce: u64,
+    entries: Vec<PositionEntry>,
+    split: &StakeSplit,
+    dust_usdc_minor: u64,
+    bump: u8,
+) -> Result<()> {
+    let stake_amount = add_entries_to_tile_pools(
+        MarketMode::Parimutuel,
+        &mut round_shard.tile_yes_pool_usdc_minor,
+        &mut round_shard.tile_no_pool_usdc_minor,
+        &entries,
+    )?;
 
-    position.round = round.key();
-    position.user = ctx.accounts.user.key();
-    position.track_index = params.track_index;
-    position.step_index = params.step_index;
-    position.will_be_active = params.will_be_active;
-    position.stake_amount_usdc_minor = params.stake_amount_usdc_minor;
+    round_shard.predictions = round_shard
+        .predictions
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round_shard.total_staked_usdc_minor = round_shard
+        .total_staked_usdc_minor
+        .checked_add(stake_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round_shard.artist_pending_usdc_minor = round_shard
+        .artist_pending_usdc_minor
+        .checked_add(split.artist_pending_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round_shard.platform_fee_usdc_minor = round_shard
+        .platform_fee_usdc_minor
+        .checked_add(split.platform_fee_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round_shard.liquidity_reserve_usdc_minor = round_shard
+        .liquidity_reserve_usdc_minor
+        .checked_add(split.liquidity_reserve_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round_shard.winner_pot_usdc_minor = round_shard
+        .winner_pot_usdc_minor
+        .checked_add(split.winner_pot_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round_shard.dust_usdc_minor = round_shard
+        .dust_usdc_minor
+        .checked_add(dust_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    init_position(
+        position,
+        round_shard.round,
+        user,
+        client_nonce,
+        entries,
+        stake_amount,
+        split,
+        bump,
+    );
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn init_position(
+    position: &mut PredictionPosition,
+    round: Pubkey,
+    user: Pubkey,
+    client_nonce: u64,
+    entries: Vec<PositionEntry>,
+    stake_amount: u64,
+    split: &StakeSplit,
+    bump: u8,
+) {
+    position.round = round;
+    position.user = user;
+    position.client_nonce = client_nonce;
+    position.stake_amount_usdc_minor = stake_amount;
+    position.artist_pending_usdc_minor = split.artist_pending_usdc_minor;
+    position.platform_fee_usdc_minor = split.platform_fee_usdc_minor;
+    position.liquidity_reserve_usdc_minor = split.liquidity_reserve_usdc_minor;
+    position.winner_pot_usdc_minor = split.winner_pot_usdc_minor;
+    position.correct_stake_usdc_minor = 0;
     position.was_correct = false;
+    position.tallied = false;
     position.usdc_payout_usdc_minor = 0;
     position.settled = false;
+    position.usdc_claimed = false;
     position.claimed = false;
-    position.bump = ctx.bumps.position;
+    position.bump = bump;
+    position.entries = entries;
+}
 
-    transfer_quote_with_authority(
-        &ctx.accounts.session_delegate,
-        &ctx.accounts.user_quote_ata,
-        &ctx.accounts.artist_pending_vault,
-        &ctx.accounts.quote_mint,
-        &ctx.accounts.token_program,
-        artist_pending,
+/// Moves a shard's unfolded totals onto the round and its escrowed stake
+/// into the room escrow, booking the legs on the room ledger.
+#[allow(clippy::too_many_arguments)]
+fn fold_round_shard<'info>(
+    room: &mut Account<'info, Room>,
+    round: &mut Round,
+    round_shard: &mut RoundShard,
+    shard_escrow: &Account<'info, TokenAccount>,
+    room_escrow: &Account<'info, TokenAccount>,
+    vault_authority: &UncheckedAccount<'info>,
+    vault_authority_bump: u8,
+    quote_mint: &Account<'info, Mint>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    if round_shard.predictions == 0 {
+        return Ok(());
+    }
+
+    let legs = StakeSplit {
+        artist_pending_usdc_minor: round_shard.artist_pending_usdc_minor,
+        platform_fee_usdc_minor: round_shard.platform_fee_usdc_minor,
+        liquidity_reserve_usdc_minor: round_shard.liquidity_reserve_usdc_minor,
+        winner_pot_usdc_minor: round_shard.winner_pot_usdc_minor,
+    };
+    transfer_quote_from_vault(
+        room,
+        vault_authority_bump,
+        vault_authority,
+        shard_escrow,
+        room_escrow,
+        quote_mint,
+        token_program,
+        stake_split_total(&legs)?,
     )?;
-    transfer_quote_with_authority(
-        &ctx.accounts.session_delegate,
+    credit_room_escrow_ledger(room, &legs)?;
+
+    round.total_predictions = round
+        .total_predictions
+        .checked_add(round_shard.predictions)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.open_positions = round
+        .open_positions
+        .checked_add(round_shard.predictions)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.total_staked_usdc_minor = round
+        .total_staked_usdc_minor
+        .checked_add(round_shard.total_staked_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.artist_pending_usdc_minor = round
+        .artist_pending_usdc_minor
+        .checked_add(legs.artist_pending_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.platform_fee_usdc_minor = round
+        .platform_fee_usdc_minor
+        .checked_add(legs.platform_fee_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.liquidity_reserve_usdc_minor = round
+        .liquidity_reserve_usdc_minor
+        .checked_add(legs.liquidity_reserve_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.winner_pot_usdc_minor = round
+        .winner_pot_usdc_minor
+        .checked_add(legs.winner_pot_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.dust_usdc_minor = round
+        .dust_usdc_minor
+        .checked_add(round_shard.dust_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    for tile in 0..TILE_COUNT {
+        round.tile_yes_pool_usdc_minor[tile] = round.tile_yes_pool_usdc_minor[tile]
+            .checked_add(round_shard.tile_yes_pool_usdc_minor[tile])
+            .ok_or(ErrorCode::MathOverflow)?;
+        round.tile_no_pool_usdc_minor[tile] = round.tile_no_pool_usdc_minor[tile]
+            .checked_add(round_shard.tile_no_pool_usdc_minor[tile])
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    round_shard.predictions = 0;
+    round_shard.total_staked_usdc_minor = 0;
+    round_shard.artist_pending_usdc_minor = 0;
+    round_shard.platform_fee_usdc_minor = 0;
+    round_shard.liquidity_reserve_usdc_minor = 0;
+    round_shard.winner_pot_usdc_minor = 0;
+    round_shard.dust_usdc_minor = 0;
+    round_shard.tile_yes_pool_usdc_minor = [0; TILE_COUNT];
+    round_shard.tile_no_pool_usdc_minor = [0; TILE_COUNT];
+    Ok(())
+}
+
+/// Folds the `(round_shard, shard_escrow)` pairs passed as remaining
+/// accounts. With `require_all`, every shard of the round must be passed,
+/// in index order, which is none at all for an LMSR round without shards.
+fn fold_remaining_round_shards<'info>(
+    accounts: &mut LockRound<'info>,
+    vault_authority_bump: u8,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    require_all: bool,
+) -> Result<()> {
+    require!(
+        remaining_accounts.len().is_multiple_of(2),
+        ErrorCode::InvalidRemainingAccounts
+    );
+    if require_all {
+        require!(
+            remaining_accounts.len() == 2 * accounts.round.shard_count as usize,
+            ErrorCode::InvalidRemainingAccounts
+        );
+    } else {
+        require!(
+            !remaining_accounts.is_empty(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+    }
+
+    let round_key = accounts.round.key();
+    for (index, pair) in remaining_accounts.chunks_exact(2).enumerate() {
+        let (round_shard_info, shard_escrow_info) = (&pair[0], &pair[1]);
+        require!(
+            round_shard_info.is_writable && shard_escrow_info.is_writable,
+            ErrorCode::InvalidRemainingAccounts
+        );
+
+        let mut round_shard = Box::new(Account::<RoundShard>::try_from(round_shard_info)?);
+        require_keys_eq!(
+            round_shard.round,
+            round_key,
+            ErrorCode::InvalidRemainingAccounts
+        );
+        require!(
+            !require_all || round_shard.index as usize == index,
+            ErrorCode::InvalidRemainingAccounts
+        );
+        let shard_escrow_key = Pubkey::create_program_address(
+            &[
+                b"round_shard_escrow",
+                round_shard_info.key.as_ref(),
+                &[round_shard.escrow_bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| error!(ErrorCode::InvalidRemainingAccounts))?;
+        require_keys_eq!(
+            shard_escrow_info.key(),
+            shard_escrow_key,
+            ErrorCode::InvalidRemainingAccounts
+        );
+        let shard_escrow = Account::<TokenAccount>::try_from(shard_escrow_info)?;
+
+        fold_round_shard(
+            &mut accounts.room,
+            &mut accounts.round,
+            &mut round_shard,
+            &shard_escrow,
+            &accounts.room_escrow,
+            &accounts.vault_authority,
+            vault_authority_bump,
+            &accounts.quote_mint,
+            &accounts.token_program,
+        )?;
+        round_shard.exit(&crate::ID)?;
+    }
+    Ok(())
+}
+
+pub fn cancel_prediction(ctx: Context<CancelPrediction>) -> Result<()> {
+    let protocol = &ctx.accounts.protocol;
+    let round = &mut ctx.accounts.round;
+    let position = &mut ctx.accounts.position;
+
+    require!(
+        round.phase == RoundPhase::PredictionOpen,
+        ErrorCode::InvalidRoundPhase
+    );
+    require!(
+        Clock::get()?.unix_timestamp < round.prediction_close_ts,
+        ErrorCode::PredictionWindowClosed
+    );
+    require_not_paused(protocol, &ctx.accounts.room, PAUSE_PREDICTIONS)?;
+    require_room_escrow_solvent(&ctx.accounts.room, ctx.accounts.room_escrow.amount)?;
+
+    // A parimutuel position may still be in its shard's unfolded totals; LMSR
+    // positions are recorded on the round directly.
+    if round.market_mode == MarketMode::Parimutuel {
+        let (Some(round_shard), Some(shard_escrow)) = (
+            ctx.accounts.round_shard.as_mut(),
+            ctx.accounts.shard_escrow.as_ref(),
+        ) else {
+            return err!(ErrorCode::RoundShardsMissing);
+        };
+        require_user_round_shard(round, round_shard, position.user)?;
+        fold_round_shard(
+            &mut ctx.accounts.room,
+            round,
+            round_shard,
+            shard_escrow,
+            &ctx.accounts.room_escrow,
+            &ctx.accounts.vault_authority,
+            ctx.bumps.vault_authority,
+            &ctx.accounts.quote_mint,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    // LMSR shares are sold back at the current price, entry by entry;
+    // parimutuel entries get their winner-pot leg back as placed.
+    let mut winner_leg = 0u64;
+    for entry in &position.entries {
+        let tile = tile_index(entry.track_index, entry.step_index)?;
+        let (tile_side, tile_other) = if entry.will_be_active {
+            (
+                round.tile_yes_pool_usdc_minor[tile],
+                round.tile_no_pool_usdc_minor[tile],
+            )
+        } else {
+            (
+                round.tile_no_pool_usdc_minor[tile],
+                round.tile_yes_pool_usdc_minor[tile],
+            )
+        };
+        let (entry_winner_leg, tile_decrement) = match round.market_mode {
+            MarketMode::Parimutuel => (entry.winner_pot_usdc_minor, entry.winner_pot_usdc_minor),
+            MarketMode::Lmsr => (
+                lmsr_sale_proceeds(
+                    tile_side,
+                    tile_other,
+                    round.lmsr_liquidity_usdc_minor,
+                    entry.shares,
+                )?,
+                entry.shares,
+            ),
+        };
+        winner_leg = winner_leg
+            .checked_add(entry_winner_leg)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let tile_pool = if entry.will_be_active {
+            &mut round.tile_yes_pool_usdc_minor[tile]
+        } else {
+            &mut round.tile_no_pool_usdc_minor[tile]
+        };
+        *tile_pool = tile_pool
+            .checked_sub(tile_decrement)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    let legs = StakeSplit {
+        artist_pending_usdc_minor: position.artist_pending_usdc_minor,
+        platform_fee_usdc_minor: position.platform_fee_usdc_minor,
+        liquidity_reserve_usdc_minor: position.liquidity_reserve_usdc_minor,
+        winner_pot_usdc_minor: winner_leg,
+    };
+    let refund = cancellation_refund(&legs, round.cancellation_fee_bps)?;
+
+    // Fees retained on each leg stay in the escrow and on the round totals.
+    round.total_staked_usdc_minor = round
+        .total_staked_usdc_minor
+        .checked_sub(position.stake_amount_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.artist_pending_usdc_minor = round
+        .artist_pending_usdc_minor
+        .checked_sub(refund.artist_pending_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.platform_fee_usdc_minor = round
+        .platform_fee_usdc_minor
+        .checked_sub(refund.platform_fee_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.liquidity_reserve_usdc_minor = round
+        .liquidity_reserve_usdc_minor
+        .checked_sub(refund.liquidity_reserve_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.winner_pot_usdc_minor = round
+        .winner_pot_usdc_minor
+        .checked_sub(refund.winner_pot_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if round.market_mode == MarketMode::Parimutuel {
+        // The retained winner-pot fee no longer belongs to a tile, so it is
+        // shared across all correct stake like carried rollover.
+        round.shared_winner_pot_usdc_minor = round
+            .shared_winner_pot_usdc_minor
+            .checked_add(legs.winner_pot_usdc_minor - refund.winner_pot_usdc_minor)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+    let kept_fees = add_stake_splits(
+        &round_cancellation_fees(round),
+        &sub_stake_splits(&legs, &refund)?,
+    )?;
+    set_round_cancellation_fees(round, &kept_fees);
+    round.cancelled_positions = round
+        .cancelled_positions
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let room = &mut ctx.accounts.room;
+    let refund_total = stake_split_total(&refund)?;
+    transfer_quote_from_vault(
+        room,
+        ctx.bumps.vault_authority,
+        &ctx.accounts.vault_authority,
+        &ctx.accounts.room_escrow,
         &ctx.accounts.user_quote_ata,
-        &ctx.accounts.platform_fee_vault,
         &ctx.accounts.quote_mint,
         &ctx.accounts.token_program,
-        platform_fee,
+        refund_total,
     )?;
-    transfer_quote_with_authority(
-        &ctx.accounts.session_delegate,
+    debit_room_escrow_ledger(room, &refund)?;
+
+    let legs_total = stake_split_total(&legs)?;
+
+    emit!(PredictionCancelled {
+        room: room.key(),
+        round: round.key(),
+        position: position.key(),
+        user: position.user,
+        refund_usdc_minor: refund_total,
+        fee_usdc_minor: legs_total - refund_total,
+    });
+
+    // The account stays behind as a tombstone so its nonce cannot be reused
+    // for the rest of the round; `close_position` reclaims the rent.
+    tombstone_cancelled_position(position);
+
+    Ok(())
+}
+
+/// Folds every shard of the round, passed as `(round_shard, shard_escrow)`
+/// remaining-account pairs in index order, so the totals are final.
+pub fn lock_round<'info>(ctx: Context<'_, '_, 'info, 'info, LockRound<'info>>) -> Result<()> {
+    require_not_paused(&ctx.accounts.protocol, &ctx.accounts.room, PAUSE_SETTLEMENT)?;
+    require!(
+        ctx.accounts.round.phase == RoundPhase::PredictionOpen,
+        ErrorCode::InvalidRoundPhase
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now >= ctx.accounts.round.prediction_close_ts,
+        ErrorCode::PredictionWindowStillOpen
+    );
+    fold_remaining_round_shards(
+        ctx.accounts,
+        ctx.bumps.vault_authority,
+        ctx.remaining_accounts,
+        true,
+    )?;
+
+    let round = &mut ctx.accounts.round;
+    round.reveal_deadline_ts = now
+        .checked_add(round.reveal_timeout_seconds)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.phase = RoundPhase::Locked;
+    Ok(())
+}
+
+/// Permissionless crank that folds any subset of shards while predictions
+/// are open, so live totals on `Round` stay close to current.
+pub fn fold_round_shards<'info>(
+    ctx: Context<'_, '_, 'info, 'info, LockRound<'info>>,
+) -> Result<()> {
+    require_not_paused(&ctx.accounts.protocol, &ctx.accounts.room, PAUSE_SETTLEMENT)?;
+    require!(
+        ctx.accounts.round.phase == RoundPhase::PredictionOpen,
+        ErrorCode::InvalidRoundPhase
+    );
+    fold_remaining_round_shards(
+        ctx.accounts,
+        ctx.bumps.vault_authority,
+        ctx.remaining_accounts,
+        false,
+    )
+}
+
+/// Not gated on `PAUSE_SETTLEMENT`: the reveal deadline keeps running while
+/// settlement is paused, so blocking the reveal would let `cancel_round` slash
+/// the bond for a non-reveal the pause caused. Revealing moves no funds.
+pub fn reveal_round(ctx: Context<MutateRound>, params: RevealRoundParams) -> Result<()> {
+    let round = &mut ctx.accounts.round;
+    require!(
+        round.phase == RoundPhase::Locked,
+        ErrorCode::InvalidRoundPhase
+    );
+
+    let reveal_hash = build_versioned_reveal_commit_hash(
+        round.commit_version,
+        &round.room,
+        round.index,
+        round.bpm,
+        &params.outcome_bitmap,
+        &params.salt,
+    )?;
+    require!(
+        reveal_hash == round.commit_hash,
+        ErrorCode::CommitHashMismatch
+    );
+
+    round.outcome_bitmap = params.outcome_bitmap;
+    round.reveal_verified = true;
+    round.phase = RoundPhase::Revealed;
+    Ok(())
+}
+
+pub fn cancel_round(ctx: Context<CancelRound>) -> Result<()> {
+    require_not_paused(&ctx.accounts.protocol, &ctx.accounts.room, PAUSE_SETTLEMENT)?;
+    let room = &mut ctx.accounts.room;
+    let round = &mut ctx.accounts.round;
+
+    require!(
+        round.phase == RoundPhase::Locked,
+        ErrorCode::InvalidRoundPhase
+    );
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now > round.reveal_deadline_ts,
+        ErrorCode::RevealDeadlineNotReached
+    );
+
+    if round.lmsr_subsidy_usdc_minor > 0 {
+        debit_room_escrow_ledger(
+            room,
+            &StakeSplit {
+                winner_pot_usdc_minor: round.lmsr_subsidy_usdc_minor,
+                ..StakeSplit::default()
+            },
+        )?;
+        credit_room_escrow_ledger(
+            room,
+            &StakeSplit {
+                liquidity_reserve_usdc_minor: round.lmsr_subsidy_usdc_minor,
+                ..StakeSplit::default()
+            },
+        )?;
+        round.winner_pot_usdc_minor = round
+            .winner_pot_usdc_minor
+            .checked_sub(round.lmsr_subsidy_usdc_minor)
+            .ok_or(ErrorCode::MathOverflow)?;
+        round.liquidity_reserve_usdc_minor = round
+            .liquidity_reserve_usdc_minor
+            .checked_add(round.lmsr_subsidy_usdc_minor)
+            .ok_or(ErrorCode::MathOverflow)?;
+        round.lmsr_subsidy_usdc_minor = 0;
+    }
+
+    // Rollover carried in from earlier rounds goes back to the room; only the
+    // stakes placed in this round are refunded to their positions.
+    room.pending_winner_rollover_usdc_minor = room
+        .pending_winner_rollover_usdc_minor
+        .checked_add(round.carried_winner_pot_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    room.pending_liquidity_rollover_usdc_minor = room
+        .pending_liquidity_rollover_usdc_minor
+        .checked_add(round.carried_liquidity_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.winner_pot_usdc_minor = round
+        .winner_pot_usdc_minor
+        .checked_sub(round.carried_winner_pot_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.liquidity_reserve_usdc_minor = round
+        .liquidity_reserve_usdc_minor
+        .checked_sub(round.carried_liquidity_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.shared_winner_pot_usdc_minor = round
+        .shared_winner_pot_usdc_minor
+        .checked_sub(round.carried_winner_pot_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.carried_winner_pot_usdc_minor = 0;
+    round.carried_liquidity_usdc_minor = 0;
+
+    // Fees kept from cancelled positions belong to no remaining position, so
+    // they are released as a settled round's legs would be. They stay in the
+    // escrow on the same legs of the ledger.
+    let kept_fees = round_cancellation_fees(round);
+    room.artist_earned_usdc_minor = room
+        .artist_earned_usdc_minor
+        .checked_add(kept_fees.artist_pending_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    room.platform_fee_accrued_usdc_minor = room
+        .platform_fee_accrued_usdc_minor
+        .checked_add(kept_fees.platform_fee_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    room.pending_liquidity_rollover_usdc_minor = room
+        .pending_liquidity_rollover_usdc_minor
+        .checked_add(kept_fees.liquidity_reserve_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    room.pending_winner_rollover_usdc_minor = room
+        .pending_winner_rollover_usdc_minor
+        .checked_add(kept_fees.winner_pot_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.artist_pending_usdc_minor = round
+        .artist_pending_usdc_minor
+        .checked_sub(kept_fees.artist_pending_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.platform_fee_usdc_minor = round
+        .platform_fee_usdc_minor
+        .checked_sub(kept_fees.platform_fee_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.liquidity_reserve_usdc_minor = round
+        .liquidity_reserve_usdc_minor
+        .checked_sub(kept_fees.liquidity_reserve_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.winner_pot_usdc_minor = round
+        .winner_pot_usdc_minor
+        .checked_sub(kept_fees.winner_pot_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if round.market_mode == MarketMode::Parimutuel {
+        round.shared_winner_pot_usdc_minor = round
+            .shared_winner_pot_usdc_minor
+            .checked_sub(kept_fees.winner_pot_usdc_minor)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+    set_round_cancellation_fees(round, &StakeSplit::default());
+
+    // Non-reveal slashes part of the artist bond to the round's stakers.
+    let slash = if round.total_staked_usdc_minor > 0 {
+        split_amount(room.artist_bond_usdc_minor, round.artist_bond_slash_bps)?
+    } else {
+        0
+    };
+    room.artist_bond_usdc_minor = room
+        .artist_bond_usdc_minor
+        .checked_sub(slash)
+        .ok_or(ErrorCode::MathOverflow)?;
+    room.bonded_rounds = room
+        .bonded_rounds
+        .checked_sub(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    round.bond_slash_usdc_minor = slash;
+    round.phase = RoundPhase::Cancelled;
+
+    if slash > 0 {
+        emit!(ArtistBondSlashed {
+            room: room.key(),
+            round: round.key(),
+            slash_usdc_minor: slash,
+            bond_usdc_minor: room.artist_bond_usdc_minor,
+        });
+    }
+
+    emit!(RoundCancelled {
+        room: room.key(),
+        round: round.key(),
+        reveal_deadline_ts: round.reveal_deadline_ts,
+        total_staked_usdc_minor: round.total_staked_usdc_minor,
+    });
+
+    Ok(())
+}
+
+pub fn refund_position(ctx: Context<RefundPosition>) -> Result<()> {
+    require_not_paused(&ctx.accounts.protocol, &ctx.accounts.room, PAUSE_CLAIMS)?;
+    require_room_escrow_solvent(&ctx.accounts.room, ctx.accounts.room_escrow.amount)?;
+    let round = &mut ctx.accounts.round;
+    let position = &mut ctx.accounts.position;
+
+    require!(
+        round.phase == RoundPhase::Cancelled,
+        ErrorCode::InvalidRoundPhase
+    );
+    require!(!position.settled, ErrorCode::PositionAlreadySettled);
+
+    let room = &mut ctx.accounts.room;
+    let vault_authority_bump = ctx.bumps.vault_authority;
+    let legs = StakeSplit {
+        artist_pending_usdc_minor: position.artist_pending_usdc_minor,
+        platform_fee_usdc_minor: position.platform_fee_usdc_minor,
+        liquidity_reserve_usdc_minor: position.liquidity_reserve_usdc_minor,
+        winner_pot_usdc_minor: position.winner_pot_usdc_minor,
+    };
+    transfer_quote_from_vault(
+        room,
+        vault_authority_bump,
+        &ctx.accounts.vault_authority,
+        &ctx.accounts.room_escrow,
         &ctx.accounts.user_quote_ata,
-        &ctx.accounts.liquidity_reserve_vault,
         &ctx.accounts.quote_mint,
         &ctx.accounts.token_program,
-        liquidity,
+        stake_split_total(&legs)?,
     )?;
-    transfer_quote_with_authority(
-        &ctx.accounts.session_delegate,
+    debit_room_escrow_ledger(room, &legs)?;
+
+    let is_last_refund = round
+        .settled_positions
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?
+        == round.total_predictions - round.cancelled_positions;
+    let compensation = pro_rata_winner_payout(
+        round.bond_slash_usdc_minor,
+        round.bond_slash_distributed_usdc_minor,
+        position.stake_amount_usdc_minor,
+        round.total_staked_usdc_minor,
+        is_last_refund,
+    )?;
+    transfer_quote_from_vault(
+        room,
+        vault_authority_bump,
+        &ctx.accounts.vault_authority,
+        &ctx.accounts.artist_bond_vault,
         &ctx.accounts.user_quote_ata,
-        &ctx.accounts.winner_pot_vault,
         &ctx.accounts.quote_mint,
         &ctx.accounts.token_program,
-        winner,
+        compensation,
     )?;
+    round.bond_slash_distributed_usdc_minor = round
+        .bond_slash_distributed_usdc_minor
+        .checked_add(compensation)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    round.settled_positions = round
+        .settled_positions
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let refund = position
+        .stake_amount_usdc_minor
+        .checked_add(compensation)
+        .ok_or(ErrorCode::MathOverflow)?;
+    position.usdc_payout_usdc_minor = refund;
+    position.settled = true;
+    position.usdc_claimed = true;
+
+    emit!(PositionRefunded {
+        room: room.key(),
+        round: round.key(),
+        position: position.key(),
+        user: position.user,
+        refund_usdc_minor: refund,
+    });
+
+    Ok(())
+}
+
+pub fn tally_position(ctx: Context<TallyPosition>) -> Result<()> {
+    require_not_paused(&ctx.accounts.protocol, &ctx.accounts.room, PAUSE_SETTLEMENT)?;
+    let round = &mut ctx.accounts.round;
+    let position = &mut ctx.accounts.position;
+
+    require!(
+        round.phase == RoundPhase::Revealed,
+        ErrorCode::InvalidRoundPhase
+    );
+    require!(!position.tallied, ErrorCode::PositionAlreadyTallied);
+
+    let mut correct_stake = 0u64;
+    for entry in position.entries.iter_mut() {
+        entry.was_correct = round.reveal_verified
+            && evaluate_prediction(
+                entry.track_index,
+                entry.step_index,
+                entry.will_be_active,
+                &round.outcome_bitmap,
+            )?;
+        if entry.was_correct {
+            correct_stake = correct_stake
+                .checked_add(entry.stake_amount_usdc_minor)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+    }
+    let is_correct = position.entries.iter().any(|entry| entry.was_correct);
+
+    if is_correct {
+        round.correct_positions = round
+            .correct_positions
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        round.correct_stake_usdc_minor = round
+            .correct_stake_usdc_minor
+            .checked_add(correct_stake)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+    round.tallied_positions = round
+        .tallied_positions
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
 
-    emit!(PredictionPlaced {
+    position.correct_stake_usdc_minor = correct_stake;
+    position.was_correct = is_correct;
+    position.tallied = true;
+    Ok(())
+}
+
+pub fn settle_position(ctx: Context<SettlePosition>) -> Result<()> {
+    require_not_paused(&ctx.accounts.protocol, &ctx.accounts.room, PAUSE_SETTLEMENT)?;
+    let round = &mut ctx.accounts.round;
+    let position = &mut ctx.accounts.position;
+
+    require_settlement_ready(round)?;
+    require!(!position.settled, ErrorCode::PositionAlreadySettled);
+
+    let payout = record_position_settlement(round, position)?;
+
+    emit!(PositionSettled {
         room: ctx.accounts.room.key(),
         round: round.key(),
-        user: ctx.accounts.user.key(),
-        stake_amount_usdc_minor: params.stake_amount_usdc_minor,
-        delegated: true,
+        position: position.key(),
+        user: position.user,
+        was_correct: position.was_correct,
+        payout_usdc_minor: payout,
     });
 
     Ok(())
 }
 
-pub fn lock_round(ctx: Context<MutateRound>) -> Result<()> {
+/// Permissionless crank: settles every position passed in
+/// `remaining_accounts` with the same payout rule as `settle_position`.
+/// Positions that are already settled are skipped so concurrent cranks do
+/// not fail each other.
+pub fn settle_positions<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SettlePositions<'info>>,
+) -> Result<()> {
+    require_not_paused(&ctx.accounts.protocol, &ctx.accounts.room, PAUSE_SETTLEMENT)?;
     let round = &mut ctx.accounts.round;
+    require_settlement_ready(round)?;
     require!(
-        round.phase == RoundPhase::PredictionOpen,
-        ErrorCode::InvalidRoundPhase
+        !ctx.remaining_accounts.is_empty(),
+        ErrorCode::InvalidRemainingAccounts
     );
-    round.phase = RoundPhase::Locked;
+
+    for position_info in ctx.remaining_accounts {
+        require!(
+            position_info.is_writable,
+            ErrorCode::InvalidRemainingAccounts
+        );
+
+        let mut position = Account::<PredictionPosition>::try_from(position_info)?;
+        require_keys_eq!(
+            position.round,
+            round.key(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+        if position.settled {
+            continue;
+        }
+
+        let payout = record_position_settlement(round, &mut position)?;
+        position.exit(&crate::ID)?;
+
+        emit!(PositionSettled {
+            room: ctx.accounts.room.key(),
+            round: round.key(),
+            position: position.key(),
+            user: position.user,
+            was_correct: position.was_correct,
+            payout_usdc_minor: payout,
+        });
+    }
+
     Ok(())
 }
 
-pub fn reveal_round(ctx: Context<MutateRound>, params: RevealRoundParams) -> Result<()> {
-    let round = &mut ctx.accounts.round;
+fn require_settlement_ready(round: &Round) -> Result<()> {
     require!(
-        round.phase == RoundPhase::Locked,
+        round.phase == RoundPhase::Revealed,
         ErrorCode::InvalidRoundPhase
     );
-
-    let reveal_hash = build_reveal_commit_hash(&params.outcome_bitmap, &params.salt);
     require!(
-        reveal_hash == round.commit_hash,
-        ErrorCode::CommitHashMismatch
+        round.tallied_positions == round.total_predictions - round.cancelled_positions,
+        ErrorCode::UntalliedPositions
     );
-
-    round.outcome_bitmap = params.outcome_bitmap;
-    round.reveal_verified = true;
-    round.phase = RoundPhase::Revealed;
     Ok(())
 }
 
-pub fn settle_position(ctx: Context<SettlePosition>, params: SettlePositionParams) -> Result<()> {
-    let round = &mut ctx.accounts.round;
-    let position = &mut ctx.accounts.position;
-
-    require!(
-        round.phase == RoundPhase::Revealed,
-        ErrorCode::InvalidRoundPhase
-    );
-    require!(!position.settled, ErrorCode::PositionAlreadySettled);
+/// Computes a tallied position's payout from the round's pools, records it
+/// on the round and marks the position settled. The payout stays in the
+/// room escrow's winner-pot leg until the user calls `claim_winnings`.
+fn record_position_settlement(round: &mut Round, position: &mut PredictionPosition) -> Result<u64> {
+    let is_correct = position.was_correct;
+    let payout = if is_correct {
+        let payout = match round.market_mode {
+            MarketMode::Lmsr => position
+                .entries
+                .iter()
+                .filter(|entry| entry.was_correct)
+                .try_fold(0u64, |total, entry| total.checked_add(entry.shares))
+                .ok_or(ErrorCode::MathOverflow)?,
+            MarketMode::Parimutuel => {
+                let mut tile_payout = 0u64;
+                for entry in position.entries.iter().filter(|entry| entry.was_correct) {
+                    let tile = tile_index(entry.track_index, entry.step_index)?;
+                    let (winning_pool, losing_pool) = if entry.will_be_active {
+                        (
+                            round.tile_yes_pool_usdc_minor[tile],
+                            round.tile_no_pool_usdc_minor[tile],
+                        )
+                    } else {
+                        (
+                            round.tile_no_pool_usdc_minor[tile],
+                            round.tile_yes_pool_usdc_minor[tile],
+                        )
+                    };
+                    tile_payout = tile_payout
+                        .checked_add(parimutuel_tile_payout(
+                            entry.winner_pot_usdc_minor,
+                            winning_pool,
+                            losing_pool,
+                        )?)
+                        .ok_or(ErrorCode::MathOverflow)?;
+                }
 
-    let is_correct = round.reveal_verified
-        && evaluate_prediction(
-            position.track_index,
-            position.step_index,
-            position.will_be_active,
-            &round.outcome_bitmap,
-        )?;
+                let is_last_winner = round
+                    .winning_positions
+                    .checked_add(1)
+                    .ok_or(ErrorCode::MathOverflow)?
+                    == round.correct_positions;
+                let shared_payout = pro_rata_winner_payout(
+                    round.shared_winner_pot_usdc_minor,
+                    round.shared_winner_pot_distributed_usdc_minor,
+                    position.correct_stake_usdc_minor,
+                    round.correct_stake_usdc_minor,
+                    is_last_winner,
+                )?;
+                round.shared_winner_pot_distributed_usdc_minor = round
+                    .shared_winner_pot_distributed_usdc_minor
+                    .checked_add(shared_payout)
+                    .ok_or(ErrorCode::MathOverflow)?;
 
-    let payout = if is_correct {
+                tile_payout
+                    .checked_add(shared_payout)
+                    .ok_or(ErrorCode::MathOverflow)?
+            }
+        };
         let remaining = round
             .winner_pot_usdc_minor
             .checked_sub(round.winner_pot_distributed_usdc_minor)
             .ok_or(ErrorCode::MathOverflow)?;
-        require!(
-            params.winner_payout_usdc_minor <= remaining,
-            ErrorCode::PayoutExceedsWinnerPot
-        );
-        transfer_quote_from_vault(
-            &ctx.accounts.room,
-            ctx.bumps.vault_authority,
-            &ctx.accounts.vault_authority,
-            &ctx.accounts.winner_pot_vault,
-            &ctx.accounts.user_quote_ata,
-            &ctx.accounts.quote_mint,
-            &ctx.accounts.token_program,
-            params.winner_payout_usdc_minor,
-        )?;
+        require!(payout <= remaining, ErrorCode::PayoutExceedsWinnerPot);
         round.winner_pot_distributed_usdc_minor = round
             .winner_pot_distributed_usdc_minor
-            .checked_add(params.winner_payout_usdc_minor)
+            .checked_add(payout)
             .ok_or(ErrorCode::MathOverflow)?;
         round.winning_positions = round
             .winning_positions
             .checked_add(1)
             .ok_or(ErrorCode::MathOverflow)?;
-        params.winner_payout_usdc_minor
+        payout
     } else {
-        require!(
-            params.winner_payout_usdc_minor == 0,
-            ErrorCode::InvalidPayoutForLosingPosition
-        );
         0
     };
 
@@ -484,24 +2082,14 @@ pub fn settle_position(ctx: Context<SettlePosition>, params: SettlePositionParam
         .checked_add(1)
         .ok_or(ErrorCode::MathOverflow)?;
 
-    position.was_correct = is_correct;
     position.usdc_payout_usdc_minor = payout;
     position.settled = true;
-
-    emit!(PositionSettled {
-        room: ctx.accounts.room.key(),
-        round: round.key(),
-        position: position.key(),
-        user: position.user,
-        was_correct: is_correct,
-        payout_usdc_minor: payout,
-    });
-
-    Ok(())
+    position.usdc_claimed = false;
+    Ok(payout)
 }
 
 pub fn settle_round(ctx: Context<SettleRound>) -> Result<()> {
-    let protocol = &ctx.accounts.protocol;
+    require_not_paused(&ctx.accounts.protocol, &ctx.accounts.room, PAUSE_SETTLEMENT)?;
     let room = &mut ctx.accounts.room;
     let round = &mut ctx.accounts.round;
 
@@ -509,28 +2097,48 @@ pub fn settle_round(ctx: Context<SettleRound>) -> Result<()> {
         round.phase == RoundPhase::Revealed,
         ErrorCode::InvalidRoundPhase
     );
-    if round.reveal_verified {
+    let remaining_winner_pot = if round.reveal_verified {
         require!(
-            round.settled_positions == round.total_predictions,
+            round.settled_positions == round.total_predictions - round.cancelled_positions,
             ErrorCode::UnsettledPositions
         );
-        let remaining_winner_pot = round
+        round
             .winner_pot_usdc_minor
             .checked_sub(round.winner_pot_distributed_usdc_minor)
-            .ok_or(ErrorCode::MathOverflow)?;
-        room.pending_winner_rollover_usdc_minor = room
-            .pending_winner_rollover_usdc_minor
-            .checked_add(remaining_winner_pot)
-            .ok_or(ErrorCode::MathOverflow)?;
+            .ok_or(ErrorCode::MathOverflow)?
     } else {
-        room.pending_winner_rollover_usdc_minor = room
-            .pending_winner_rollover_usdc_minor
-            .checked_add(round.winner_pot_usdc_minor)
+        round.winner_pot_usdc_minor
+    };
+
+    // Whatever is left of the LMSR subsidy goes back to the liquidity reserve.
+    let subsidy_return = remaining_winner_pot.min(round.lmsr_subsidy_usdc_minor);
+    if subsidy_return > 0 {
+        debit_room_escrow_ledger(
+            room,
+            &StakeSplit {
+                winner_pot_usdc_minor: subsidy_return,
+                ..StakeSplit::default()
+            },
+        )?;
+        credit_room_escrow_ledger(
+            room,
+            &StakeSplit {
+                liquidity_reserve_usdc_minor: subsidy_return,
+                ..StakeSplit::default()
+            },
+        )?;
+        round.liquidity_reserve_usdc_minor = round
+            .liquidity_reserve_usdc_minor
+            .checked_add(subsidy_return)
             .ok_or(ErrorCode::MathOverflow)?;
     }
+    room.pending_winner_rollover_usdc_minor = room
+        .pending_winner_rollover_usdc_minor
+        .checked_add(remaining_winner_pot - subsidy_return)
+        .ok_or(ErrorCode::MathOverflow)?;
 
     if round.liquidity_reserve_usdc_minor > 0
-        && round.liquidity_reserve_usdc_minor < protocol.min_launch_quote_usdc_minor
+        && round.liquidity_reserve_usdc_minor < round.min_launch_quote_usdc_minor
     {
         let artist_boost = round
             .liquidity_reserve_usdc_minor
@@ -541,16 +2149,19 @@ pub fn settle_round(ctx: Context<SettleRound>) -> Result<()> {
             .checked_sub(artist_boost)
             .ok_or(ErrorCode::MathOverflow)?;
 
-        let room_ref = &*room;
-        transfer_quote_from_vault(
-            room_ref,
-            ctx.bumps.vault_authority,
-            &ctx.accounts.vault_authority,
-            &ctx.accounts.liquidity_reserve_vault,
-            &ctx.accounts.artist_pending_vault,
-            &ctx.accounts.quote_mint,
-            &ctx.accounts.token_program,
-            artist_boost,
+        debit_room_escrow_ledger(
+            room,
+            &StakeSplit {
+                liquidity_reserve_usdc_minor: artist_boost,
+                ..StakeSplit::default()
+            },
+        )?;
+        credit_room_escrow_ledger(
+            room,
+            &StakeSplit {
+                artist_pending_usdc_minor: artist_boost,
+                ..StakeSplit::default()
+            },
         )?;
 
         round.artist_pending_usdc_minor = round
@@ -568,6 +2179,18 @@ pub fn settle_round(ctx: Context<SettleRound>) -> Result<()> {
             .ok_or(ErrorCode::MathOverflow)?;
     }
 
+    room.platform_fee_accrued_usdc_minor = room
+        .platform_fee_accrued_usdc_minor
+        .checked_add(round.platform_fee_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    room.artist_earned_usdc_minor = room
+        .artist_earned_usdc_minor
+        .checked_add(round.artist_pending_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    room.bonded_rounds = room
+        .bonded_rounds
+        .checked_sub(1)
+        .ok_or(ErrorCode::MathOverflow)?;
     round.phase = RoundPhase::Settled;
 
     emit!(RoundSettled {
@@ -580,23 +2203,332 @@ pub fn settle_round(ctx: Context<SettleRound>) -> Result<()> {
     Ok(())
 }
 
+pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
+    require_not_paused(&ctx.accounts.protocol, &ctx.accounts.room, PAUSE_CLAIMS)?;
+    require_room_escrow_solvent(&ctx.accounts.room, ctx.accounts.room_escrow.amount)?;
+    let position = &mut ctx.accounts.position;
+
+    require!(
+        ctx.accounts.round.phase == RoundPhase::Settled,
+        ErrorCode::InvalidRoundPhase
+    );
+    require!(position.settled, ErrorCode::PositionNotSettled);
+    require!(!position.usdc_claimed, ErrorCode::WinningsAlreadyClaimed);
+    require!(
+        position.usdc_payout_usdc_minor > 0,
+        ErrorCode::NoWinningsToClaim
+    );
+
+    transfer_quote_from_vault(
+        &ctx.accounts.room,
+        ctx.bumps.vault_authority,
+        &ctx.accounts.vault_authority,
+        &ctx.accounts.room_escrow,
+        &ctx.accounts.user_quote_ata,
+        &ctx.accounts.quote_mint,
+        &ctx.accounts.token_program,
+        position.usdc_payout_usdc_minor,
+    )?;
+    debit_room_escrow_ledger(
+        &mut ctx.accounts.room,
+        &StakeSplit {
+            winner_pot_usdc_minor: position.usdc_payout_usdc_minor,
+            ..StakeSplit::default()
+        },
+    )?;
+    position.usdc_claimed = true;
+
+    emit!(WinningsClaimed {
+        room: ctx.accounts.room.key(),
+        round: ctx.accounts.round.key(),
+        position: position.key(),
+        user: position.user,
+        amount_usdc_minor: position.usdc_payout_usdc_minor,
+    });
+
+    Ok(())
+}
+
+pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+    let round = &mut ctx.accounts.round;
+    let position = &ctx.accounts.position;
+
+    require!(
+        matches!(round.phase, RoundPhase::Settled | RoundPhase::Cancelled),
+        ErrorCode::InvalidRoundPhase
+    );
+    require!(position.settled, ErrorCode::PositionNotSettled);
+    require!(
+        (position.usdc_claimed || position.usdc_payout_usdc_minor == 0)
+            && (position.claimed || !position.was_correct),
+        ErrorCode::PositionHasUnclaimedBalance
+    );
+
+    round.open_positions = round
+        .open_positions
+        .checked_sub(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(())
+}
+
+pub fn close_round(ctx: Context<CloseRound>) -> Result<()> {
+    let round = &ctx.accounts.round;
+    require!(
+        matches!(round.phase, RoundPhase::Settled | RoundPhase::Cancelled),
+        ErrorCode::InvalidRoundPhase
+    );
+    require!(round.open_positions == 0, ErrorCode::OpenPositionsRemain);
+    require!(round.shard_count == 0, ErrorCode::RoundShardsRemain);
+    Ok(())
+}
+
+/// Closes a shard once its round is over. Every shard was folded at lock,
+/// so only stray transfers can be left in its escrow; they go to the room
+/// escrow as surplus.
+pub fn close_round_shard(ctx: Context<CloseRoundShard>) -> Result<()> {
+    require!(
+        matches!(
+            ctx.accounts.round.phase,
+            RoundPhase::Settled | RoundPhase::Cancelled
+        ),
+        ErrorCode::InvalidRoundPhase
+    );
+
+    let vault_authority_bump = ctx.bumps.vault_authority;
+    transfer_quote_from_vault(
+        &ctx.accounts.room,
+        vault_authority_bump,
+        &ctx.accounts.vault_authority,
+        &ctx.accounts.shard_escrow,
+        &ctx.accounts.room_escrow,
+        &ctx.accounts.quote_mint,
+        &ctx.accounts.token_program,
+        ctx.accounts.shard_escrow.amount,
+    )?;
+    close_vault_token_account(
+        &ctx.accounts.room,
+        vault_authority_bump,
+        &ctx.accounts.vault_authority,
+        &ctx.accounts.shard_escrow,
+        &ctx.accounts.artist.to_account_info(),
+        &ctx.accounts.token_program,
+    )?;
+
+    let round = &mut ctx.accounts.round;
+    round.shard_count = round
+        .shard_count
+        .checked_sub(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(())
+}
+
 pub fn claim_artist_pending(
     ctx: Context<ClaimArtistPending>,
     amount_usdc_minor: u64,
 ) -> Result<()> {
+    require_not_paused(&ctx.accounts.protocol, &ctx.accounts.room, PAUSE_CLAIMS)?;
+    require_room_escrow_solvent(&ctx.accounts.room, ctx.accounts.room_escrow.amount)?;
+    let claimable = ctx
+        .accounts
+        .room
+        .artist_earned_usdc_minor
+        .checked_sub(ctx.accounts.room.artist_claimed_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(
+        amount_usdc_minor > 0 && amount_usdc_minor <= claimable,
+        ErrorCode::ArtistClaimExceedsEarned
+    );
+
     transfer_quote_from_vault(
         &ctx.accounts.room,
         ctx.bumps.vault_authority,
         &ctx.accounts.vault_authority,
-        &ctx.accounts.artist_pending_vault,
+        &ctx.accounts.room_escrow,
         &ctx.accounts.artist_quote_ata,
         &ctx.accounts.quote_mint,
         &ctx.accounts.token_program,
         amount_usdc_minor,
-    )
+    )?;
+
+    let room = &mut ctx.accounts.room;
+    debit_room_escrow_ledger(
+        room,
+        &StakeSplit {
+            artist_pending_usdc_minor: amount_usdc_minor,
+            ..StakeSplit::default()
+        },
+    )?;
+    room.artist_claimed_usdc_minor = room
+        .artist_claimed_usdc_minor
+        .checked_add(amount_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    emit!(ArtistPendingClaimed {
+        room: room.key(),
+        artist: ctx.accounts.artist.key(),
+        amount_usdc_minor,
+        artist_earned_usdc_minor: room.artist_earned_usdc_minor,
+        artist_claimed_usdc_minor: room.artist_claimed_usdc_minor,
+    });
+
+    Ok(())
+}
+
+/// Compares the room escrow balance with the liabilities booked against
+/// it. Changes no state; the report is emitted and returned.
+pub fn reconcile_room_escrow(ctx: Context<ReconcileRoomEscrow>) -> Result<EscrowReconciliation> {
+    let room = &ctx.accounts.room;
+    let escrow_balance = ctx.accounts.room_escrow.amount;
+    let liabilities = room_escrow_liabilities(room);
+    let (shortfall, surplus) = escrow_shortfall_and_surplus(escrow_balance, &liabilities)?;
+    let solvent = shortfall == 0;
+
+    emit!(RoomEscrowReconciled {
+        room: room.key(),
+        escrow_balance_usdc_minor: escrow_balance,
+        liabilities_usdc_minor: stake_split_total(&liabilities)?,
+        shortfall_usdc_minor: shortfall,
+        surplus_usdc_minor: surplus,
+        solvent,
+    });
+
+    Ok(EscrowReconciliation {
+        room: room.key(),
+        escrow_balance_usdc_minor: escrow_balance,
+        artist_pending_liability_usdc_minor: liabilities.artist_pending_usdc_minor,
+        platform_fee_liability_usdc_minor: liabilities.platform_fee_usdc_minor,
+        liquidity_reserve_liability_usdc_minor: liabilities.liquidity_reserve_usdc_minor,
+        winner_pot_liability_usdc_minor: liabilities.winner_pot_usdc_minor,
+        shortfall_usdc_minor: shortfall,
+        surplus_usdc_minor: surplus,
+        solvent,
+    })
+}
+
+pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+    let protocol_treasury = &mut ctx.accounts.protocol_treasury;
+    protocol_treasury.protocol = ctx.accounts.protocol.key();
+    protocol_treasury.total_swept_usdc_minor = 0;
+    protocol_treasury.total_withdrawn_usdc_minor = 0;
+    protocol_treasury.bump = ctx.bumps.protocol_treasury;
+    Ok(())
 }
 
+/// Moves each room's accrued platform fees into the treasury. Amounts come
+/// from the room ledger, never from the vault balance.
+pub fn sweep_platform_fees<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SweepPlatformFees<'info>>,
+) -> Result<()> {
+    let protocol = &ctx.accounts.protocol;
+    let remaining_accounts = ctx.remaining_accounts;
+    require!(
+        protocol.pause_flags & PAUSE_CLAIMS == 0,
+        ErrorCode::ProtocolPaused
+    );
+    require!(
+        !remaining_accounts.is_empty() && remaining_accounts.len().is_multiple_of(3),
+        ErrorCode::InvalidRemainingAccounts
+    );
+
+    let mut total_swept = 0u64;
+    for triple in remaining_accounts.chunks_exact(3) {
+        let (room_info, vault_authority_info, room_escrow_info) =
+            (&triple[0], &triple[1], &triple[2]);
+        require!(
+            room_info.is_writable && room_escrow_info.is_writable,
+            ErrorCode::InvalidRemainingAccounts
+        );
+
+        let mut room = Account::<Room>::try_from(room_info)?;
+        require_keys_eq!(
+            room.protocol,
+            protocol.key(),
+            ErrorCode::InvalidRoomProtocol
+        );
+        // One paused, empty or insolvent room must not fail the whole
+        // cross-room sweep.
+        let claims_paused = room.pause_flags & PAUSE_CLAIMS != 0;
+        if claims_paused || room.platform_fee_accrued_usdc_minor == 0 {
+            emit!(PlatformFeeSweepSkipped {
+                room: room.key(),
+                claims_paused,
+                insolvent: false,
+            });
+            continue;
+        }
+
+        let (vault_authority_key, vault_authority_bump) =
+            Pubkey::find_program_address(&[b"vault_authority", room.key().as_ref()], &crate::ID);
+        require_keys_eq!(
+            vault_authority_info.key(),
+            vault_authority_key,
+            ErrorCode::InvalidRemainingAccounts
+        );
+        require_keys_eq!(
+            room_escrow_info.key(),
+            get_associated_token_address(&vault_authority_key, &protocol.quote_mint),
+            ErrorCode::InvalidRemainingAccounts
+        );
+        let vault_authority = UncheckedAccount::try_from(vault_authority_info);
+        let room_escrow = Account::<TokenAccount>::try_from(room_escrow_info)?;
+        if require_room_escrow_solvent(&room, room_escrow.amount).is_err() {
+            emit!(PlatformFeeSweepSkipped {
+                room: room.key(),
+                claims_paused,
+                insolvent: true,
+            });
+            continue;
+        }
+
+        let amount = room.platform_fee_accrued_usdc_minor;
+        transfer_quote_from_vault(
+            &room,
+            vault_authority_bump,
+            &vault_authority,
+            &room_escrow,
+            &ctx.accounts.treasury_quote_vault,
+            &ctx.accounts.quote_mint,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        debit_room_escrow_ledger(
+            &mut room,
+            &StakeSplit {
+                platform_fee_usdc_minor: amount,
+                ..StakeSplit::default()
+            },
+        )?;
+        room.platform_fee_accrued_usdc_minor = 0;
+        room.exit(&crate::ID)?;
+        total_swept = total_swept
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(PlatformFeesSwept {
+            room: room.key(),
+            amount_usdc_minor: amount,
+        });
+    }
+
+    let protocol_treasury = &mut ctx.accounts.protocol_treasury;
+    protocol_treasury.total_swept_usdc_minor = protocol_treasury
+        .total_swept_usdc_minor
+        .checked_add(total_swept)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(())
+}
+
+/// Pays part of one room's accrued platform fees straight to the treasury
+/// destination, from the same ledger `sweep_platform_fees` drains.
 pub fn claim_platform_fee(ctx: Context<ClaimPlatformFee>, amount_usdc_minor: u64) -> Result<()> {
+    require_not_paused(&ctx.accounts.protocol, &ctx.accounts.room, PAUSE_CLAIMS)?;
+    require_room_escrow_solvent(&ctx.accounts.room, ctx.accounts.platform_fee_vault.amount)?;
+    require!(
+        amount_usdc_minor > 0
+            && amount_usdc_minor <= ctx.accounts.room.platform_fee_accrued_usdc_minor,
+        ErrorCode::PlatformFeeClaimExceedsAccrued
+    );
+
     transfer_quote_from_vault(
         &ctx.accounts.room,
         ctx.bumps.vault_authority,
@@ -606,10 +2538,62 @@ pub fn claim_platform_fee(ctx: Context<ClaimPlatformFee>, amount_usdc_minor: u64
         &ctx.accounts.quote_mint,
         &ctx.accounts.token_program,
         amount_usdc_minor,
-    )
+    )?;
+
+    let room = &mut ctx.accounts.room;
+    debit_room_escrow_ledger(
+        room,
+        &StakeSplit {
+            platform_fee_usdc_minor: amount_usdc_minor,
+            ..StakeSplit::default()
+        },
+    )?;
+    room.platform_fee_accrued_usdc_minor = room
+        .platform_fee_accrued_usdc_minor
+        .checked_sub(amount_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    emit!(PlatformFeeClaimed {
+        room: room.key(),
+        destination: ctx.accounts.platform_treasury_quote_ata.key(),
+        amount_usdc_minor,
+        platform_fee_accrued_usdc_minor: room.platform_fee_accrued_usdc_minor,
+    });
+
+    Ok(())
+}
+
+pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount_usdc_minor: u64) -> Result<()> {
+    require!(
+        ctx.accounts.protocol.pause_flags & PAUSE_CLAIMS == 0,
+        ErrorCode::ProtocolPaused
+    );
+
+    transfer_quote_from_treasury(
+        &ctx.accounts.protocol_treasury,
+        &ctx.accounts.treasury_quote_vault,
+        &ctx.accounts.treasury_destination,
+        &ctx.accounts.quote_mint,
+        &ctx.accounts.token_program,
+        amount_usdc_minor,
+    )?;
+
+    let protocol_treasury = &mut ctx.accounts.protocol_treasury;
+    protocol_treasury.total_withdrawn_usdc_minor = protocol_treasury
+        .total_withdrawn_usdc_minor
+        .checked_add(amount_usdc_minor)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    emit!(TreasuryWithdrawn {
+        destination: ctx.accounts.treasury_destination.key(),
+        amount_usdc_minor,
+    });
+
+    Ok(())
 }
 
 pub fn claim_reward_token(ctx: Context<ClaimRewardToken>) -> Result<()> {
+    require_not_paused(&ctx.accounts.protocol, &ctx.accounts.room, PAUSE_CLAIMS)?;
     let position = &mut ctx.accounts.position;
     require!(position.settled, ErrorCode::PositionNotSettled);
     require!(position.was_correct, ErrorCode::PositionNotRewardEligible);
@@ -622,7 +2606,15 @@ pub fn claim_reward_token(ctx: Context<ClaimRewardToken>) -> Result<()> {
         ErrorCode::RewardMintAuthorityMismatch
     );
 
-    let reward_amount = one_token_amount(ctx.accounts.reward_mint.decimals)?;
+    // One reward token per correct entry.
+    let correct_entries = position
+        .entries
+        .iter()
+        .filter(|entry| entry.was_correct)
+        .count() as u64;
+    let reward_amount = one_token_amount(ctx.accounts.reward_mint.decimals)?
+        .checked_mul(correct_entries)
+        .ok_or(ErrorCode::MathOverflow)?;
     mint_reward_from_vault_authority(
         &ctx.accounts.room,
         ctx.bumps.vault_authority,
@@ -650,6 +2642,8 @@ pub fn deploy_liquidity_reserve(
     ctx: Context<DeployLiquidityReserve>,
     amount_usdc_minor: u64,
 ) -> Result<()> {
+    require_not_paused(&ctx.accounts.protocol, &ctx.accounts.room, PAUSE_LIQUIDITY)?;
+    require_room_escrow_solvent(&ctx.accounts.room, ctx.accounts.room_escrow.amount)?;
     let room = &mut ctx.accounts.room;
     require!(
         room.pending_liquidity_rollover_usdc_minor >= amount_usdc_minor,
@@ -660,13 +2654,20 @@ pub fn deploy_liquidity_reserve(
         room,
         ctx.bumps.vault_authority,
         &ctx.accounts.vault_authority,
-        &ctx.accounts.liquidity_reserve_vault,
+        &ctx.accounts.room_escrow,
         &ctx.accounts.destination_quote_ata,
         &ctx.accounts.quote_mint,
         &ctx.accounts.token_program,
         amount_usdc_minor,
     )?;
 
+    debit_room_escrow_ledger(
+        room,
+        &StakeSplit {
+            liquidity_reserve_usdc_minor: amount_usdc_minor,
+            ..StakeSplit::default()
+        },
+    )?;
     room.pending_liquidity_rollover_usdc_minor = room
         .pending_liquidity_rollover_usdc_minor
         .checked_sub(amount_usdc_minor)
@@ -1,4 +1,4 @@
-#![allow(unexpected_cfgs)]
+#![allow(unexpected_cfgs, deprecated)]
 
 use anchor_lang::prelude::*;
 
@@ -27,25 +27,115 @@ pub mod jamming_prediction {
         instructions::initialize_protocol(ctx, params)
     }
 
-    pub fn update_protocol_config(
-        ctx: Context<UpdateProtocolConfig>,
+    pub fn propose_protocol_config(
+        ctx: Context<ProposeProtocolConfig>,
         params: UpdateProtocolConfigParams,
     ) -> Result<()> {
-        instructions::update_protocol_config(ctx, params)
+        instructions::propose_protocol_config(ctx, params)
     }
 
-    pub fn set_protocol_paused(ctx: Context<SetProtocolPaused>, paused: bool) -> Result<()> {
-        instructions::set_protocol_paused(ctx, paused)
+    pub fn execute_protocol_config(ctx: Context<ResolveProtocolConfig>) -> Result<()> {
+        instructions::execute_protocol_config(ctx)
+    }
+
+    pub fn cancel_protocol_config(ctx: Context<ResolveProtocolConfig>) -> Result<()> {
+        instructions::cancel_protocol_config(ctx)
+    }
+
+    pub fn set_protocol_roles(
+        ctx: Context<SetProtocolRoles>,
+        params: SetProtocolRolesParams,
+    ) -> Result<()> {
+        instructions::set_protocol_roles(ctx, params)
+    }
+
+    pub fn set_prediction_delegate(
+        ctx: Context<SetPredictionDelegate>,
+        prediction_delegate: Pubkey,
+        delegate_max_stake_usdc_minor: u64,
+    ) -> Result<()> {
+        instructions::set_prediction_delegate(
+            ctx,
+            prediction_delegate,
+            delegate_max_stake_usdc_minor,
+        )
+    }
+
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        instructions::propose_admin(ctx, new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin(ctx)
+    }
+
+    pub fn set_protocol_pause_flags(
+        ctx: Context<SetProtocolPauseFlags>,
+        pause_flags: u8,
+    ) -> Result<()> {
+        instructions::set_protocol_pause_flags(ctx, pause_flags)
+    }
+
+    pub fn set_room_pause_flags(ctx: Context<SetRoomPauseFlags>, pause_flags: u8) -> Result<()> {
+        instructions::set_room_pause_flags(ctx, pause_flags)
     }
 
     pub fn create_room(ctx: Context<CreateRoom>, params: CreateRoomParams) -> Result<()> {
         instructions::create_room(ctx, params)
     }
 
+    pub fn set_room_fee_override(
+        ctx: Context<SetRoomFeeOverride>,
+        params: SetRoomFeeOverrideParams,
+    ) -> Result<()> {
+        instructions::set_room_fee_override(ctx, params)
+    }
+
+    pub fn remove_room_fee_override(ctx: Context<RemoveRoomFeeOverride>) -> Result<()> {
+        instructions::remove_room_fee_override(ctx)
+    }
+
+    pub fn set_room_market_mode(
+        ctx: Context<SetRoomMarketMode>,
+        params: SetRoomMarketModeParams,
+    ) -> Result<()> {
+        instructions::set_room_market_mode(ctx, params)
+    }
+
+    pub fn add_room_operator(ctx: Context<ManageRoomOperators>, operator: Pubkey) -> Result<()> {
+        instructions::add_room_operator(ctx, operator)
+    }
+
+    pub fn remove_room_operator(ctx: Context<ManageRoomOperators>, operator: Pubkey) -> Result<()> {
+        instructions::remove_room_operator(ctx, operator)
+    }
+
+    pub fn deposit_artist_bond(
+        ctx: Context<DepositArtistBond>,
+        amount_usdc_minor: u64,
+    ) -> Result<()> {
+        instructions::deposit_artist_bond(ctx, amount_usdc_minor)
+    }
+
+    pub fn request_artist_bond_withdrawal(
+        ctx: Context<RequestArtistBondWithdrawal>,
+        amount_usdc_minor: u64,
+    ) -> Result<()> {
+        instructions::request_artist_bond_withdrawal(ctx, amount_usdc_minor)
+    }
+
+    pub fn withdraw_artist_bond(ctx: Context<WithdrawArtistBond>) -> Result<()> {
+        instructions::withdraw_artist_bond(ctx)
+    }
+
     pub fn start_round(ctx: Context<StartRound>, params: StartRoundParams) -> Result<()> {
         instructions::start_round(ctx, params)
     }
 
+    pub fn initialize_round_shard(ctx: Context<InitializeRoundShard>) -> Result<()> {
+        instructions::initialize_round_shard(ctx)
+    }
+
     pub fn commit_round(ctx: Context<CommitRound>, params: CommitRoundParams) -> Result<()> {
         instructions::commit_round(ctx, params)
     }
@@ -64,25 +154,77 @@ pub mod jamming_prediction {
         instructions::place_prediction_delegated(ctx, params)
     }
 
-    pub fn lock_round(ctx: Context<MutateRound>) -> Result<()> {
+    pub fn place_prediction_batch(
+        ctx: Context<PlacePredictionBatch>,
+        params: PlacePredictionBatchParams,
+    ) -> Result<()> {
+        instructions::place_prediction_batch(ctx, params)
+    }
+
+    pub fn buy_tile_shares(ctx: Context<BuyTileShares>, params: BuyTileSharesParams) -> Result<()> {
+        instructions::buy_tile_shares(ctx, params)
+    }
+
+    pub fn cancel_prediction(ctx: Context<CancelPrediction>) -> Result<()> {
+        instructions::cancel_prediction(ctx)
+    }
+
+    pub fn lock_round<'info>(ctx: Context<'_, '_, 'info, 'info, LockRound<'info>>) -> Result<()> {
         instructions::lock_round(ctx)
     }
 
+    pub fn fold_round_shards<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LockRound<'info>>,
+    ) -> Result<()> {
+        instructions::fold_round_shards(ctx)
+    }
+
     pub fn reveal_round(ctx: Context<MutateRound>, params: RevealRoundParams) -> Result<()> {
         instructions::reveal_round(ctx, params)
     }
 
-    pub fn settle_position(
-        ctx: Context<SettlePosition>,
-        params: SettlePositionParams,
+    pub fn cancel_round(ctx: Context<CancelRound>) -> Result<()> {
+        instructions::cancel_round(ctx)
+    }
+
+    pub fn refund_position(ctx: Context<RefundPosition>) -> Result<()> {
+        instructions::refund_position(ctx)
+    }
+
+    pub fn tally_position(ctx: Context<TallyPosition>) -> Result<()> {
+        instructions::tally_position(ctx)
+    }
+
+    pub fn settle_position(ctx: Context<SettlePosition>) -> Result<()> {
+        instructions::settle_position(ctx)
+    }
+
+    pub fn settle_positions<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettlePositions<'info>>,
     ) -> Result<()> {
-        instructions::settle_position(ctx, params)
+        instructions::settle_positions(ctx)
     }
 
     pub fn settle_round(ctx: Context<SettleRound>) -> Result<()> {
         instructions::settle_round(ctx)
     }
 
+    pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
+        instructions::claim_winnings(ctx)
+    }
+
+    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+        instructions::close_position(ctx)
+    }
+
+    pub fn close_round(ctx: Context<CloseRound>) -> Result<()> {
+        instructions::close_round(ctx)
+    }
+
+    pub fn close_round_shard(ctx: Context<CloseRoundShard>) -> Result<()> {
+        instructions::close_round_shard(ctx)
+    }
+
     pub fn claim_artist_pending(
         ctx: Context<ClaimArtistPending>,
         amount_usdc_minor: u64,
@@ -90,6 +232,22 @@ pub mod jamming_prediction {
         instructions::claim_artist_pending(ctx, amount_usdc_minor)
     }
 
+    pub fn reconcile_room_escrow(
+        ctx: Context<ReconcileRoomEscrow>,
+    ) -> Result<state::EscrowReconciliation> {
+        instructions::reconcile_room_escrow(ctx)
+    }
+
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+        instructions::initialize_treasury(ctx)
+    }
+
+    pub fn sweep_platform_fees<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SweepPlatformFees<'info>>,
+    ) -> Result<()> {
+        instructions::sweep_platform_fees(ctx)
+    }
+
     pub fn claim_platform_fee(
         ctx: Context<ClaimPlatformFee>,
         amount_usdc_minor: u64,
@@ -97,6 +255,10 @@ pub mod jamming_prediction {
         instructions::claim_platform_fee(ctx, amount_usdc_minor)
     }
 
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount_usdc_minor: u64) -> Result<()> {
+        instructions::withdraw_treasury(ctx, amount_usdc_minor)
+    }
+
     pub fn claim_reward_token(ctx: Context<ClaimRewardToken>) -> Result<()> {
         instructions::claim_reward_token(ctx)
     }
@@ -1,6 +1,9 @@
 use anchor_lang::prelude::*;
 
-use crate::constants::REVEAL_BITMAP_BYTES;
+use crate::{
+    constants::REVEAL_BITMAP_BYTES,
+    state::{DustDestination, MarketMode},
+};
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct InitializeProtocolParams {
@@ -14,9 +17,19 @@ pub struct InitializeProtocolParams {
     pub min_launch_quote_usdc_minor: u64,
     pub prediction_delegate: Pubkey,
     pub delegate_max_stake_usdc_minor: u64,
+    pub reveal_timeout_seconds: i64,
+    pub min_artist_bond_usdc_minor: u64,
+    pub artist_bond_slash_bps: u16,
+    pub bond_withdrawal_cooldown_seconds: i64,
+    pub cancellation_fee_bps: u16,
+    pub config_timelock_seconds: i64,
+    pub treasury_destination: Pubkey,
+    pub dust_destination: DustDestination,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize)]
+/// Config fields that can be changed after initialisation, through
+/// `propose_protocol_config` / `execute_protocol_config`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct UpdateProtocolConfigParams {
     pub platform_fee_bps: u16,
     pub artist_pending_bps: u16,
@@ -25,14 +38,49 @@ pub struct UpdateProtocolConfigParams {
     pub min_stake_usdc_minor: u64,
     pub max_stake_usdc_minor: u64,
     pub min_launch_quote_usdc_minor: u64,
-    pub prediction_delegate: Pubkey,
-    pub delegate_max_stake_usdc_minor: u64,
+    pub reveal_timeout_seconds: i64,
+    pub min_artist_bond_usdc_minor: u64,
+    pub artist_bond_slash_bps: u16,
+    pub bond_withdrawal_cooldown_seconds: i64,
+    pub cancellation_fee_bps: u16,
+    pub config_timelock_seconds: i64,
+    pub treasury_destination: Pubkey,
+    pub dust_destination: DustDestination,
+}
+
+impl UpdateProtocolConfigParams {
+    pub const LEN: usize = 2 + 2 + 2 + 2 + 8 + 8 + 8 + 8 + 8 + 2 + 8 + 2 + 8 + 32 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct SetProtocolRolesParams {
+    pub pauser: Pubkey,
+    pub fee_manager: Pubkey,
+    pub treasury: Pubkey,
+    pub liquidity_deployer: Pubkey,
+    pub delegate_manager: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct SetRoomFeeOverrideParams {
+    pub platform_fee_bps: u16,
+    pub artist_pending_bps: u16,
+    pub liquidity_reserve_bps: u16,
+    pub winner_pot_bps: u16,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct CreateRoomParams {
     pub room_code: [u8; 8],
     pub room_token_symbol: [u8; 12],
+    pub market_mode: MarketMode,
+    pub lmsr_liquidity_usdc_minor: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct SetRoomMarketModeParams {
+    pub market_mode: MarketMode,
+    pub lmsr_liquidity_usdc_minor: u64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -42,7 +90,18 @@ pub struct StartRoundParams {
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct CommitRoundParams {
+    pub commit_version: u8,
     pub commit_hash: [u8; 32],
+    pub prediction_close_ts: i64,
+}
+
+/// One tile prediction inside a batch.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PredictionEntryParams {
+    pub track_index: u8,
+    pub step_index: u8,
+    pub will_be_active: bool,
+    pub stake_amount_usdc_minor: u64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -51,15 +110,42 @@ pub struct PlacePredictionParams {
     pub step_index: u8,
     pub will_be_active: bool,
     pub stake_amount_usdc_minor: u64,
+    /// Chosen by the client, unique per user and round; seeds the position
+    /// PDA, so resending the same placement fails instead of placing twice.
+    pub client_nonce: u64,
+}
+
+impl PlacePredictionParams {
+    pub fn entry(&self) -> PredictionEntryParams {
+        PredictionEntryParams {
+            track_index: self.track_index,
+            step_index: self.step_index,
+            will_be_active: self.will_be_active,
+            stake_amount_usdc_minor: self.stake_amount_usdc_minor,
+        }
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
-pub struct RevealRoundParams {
-    pub outcome_bitmap: [u8; REVEAL_BITMAP_BYTES],
-    pub salt: [u8; 32],
+pub struct PlacePredictionBatchParams {
+    pub entries: Vec<PredictionEntryParams>,
+    /// See `PlacePredictionParams::client_nonce`.
+    pub client_nonce: u64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
-pub struct SettlePositionParams {
-    pub winner_payout_usdc_minor: u64,
+pub struct BuyTileSharesParams {
+    pub track_index: u8,
+    pub step_index: u8,
+    pub will_be_active: bool,
+    pub stake_amount_usdc_minor: u64,
+    pub min_shares: u64,
+    /// See `PlacePredictionParams::client_nonce`.
+    pub client_nonce: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct RevealRoundParams {
+    pub outcome_bitmap: [u8; REVEAL_BITMAP_BYTES],
+    pub salt: [u8; 32],
 }
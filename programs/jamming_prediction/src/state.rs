@@ -1,6 +1,9 @@
 use anchor_lang::prelude::*;
 
-use crate::constants::REVEAL_BITMAP_BYTES;
+use crate::{
+    constants::{MAX_ROOM_OPERATORS, REVEAL_BITMAP_BYTES, TILE_COUNT},
+    params::UpdateProtocolConfigParams,
+};
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
 pub enum RoundPhase {
@@ -9,11 +12,38 @@ pub enum RoundPhase {
     Locked,
     Revealed,
     Settled,
+    Cancelled,
+}
+
+/// Stake leg that receives the rounding dust left after flooring each
+/// leg's bps share.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DustDestination {
+    WinnerPot,
+    ArtistPending,
+    PlatformFee,
+    LiquidityReserve,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MarketMode {
+    Parimutuel,
+    Lmsr,
 }
 
 #[account]
 pub struct ProtocolConfig {
+    /// Manages roles and admin handover only; every other operation is
+    /// gated by its own role key.
     pub admin: Pubkey,
+    pub pauser: Pubkey,
+    /// Proposes, executes and cancels timelocked config changes.
+    pub fee_manager: Pubkey,
+    /// Withdraws from the protocol treasury.
+    pub treasury: Pubkey,
+    pub liquidity_deployer: Pubkey,
+    /// Sets `prediction_delegate` and its stake caps.
+    pub delegate_manager: Pubkey,
     pub quote_mint: Pubkey,
     pub platform_fee_bps: u16,
     pub artist_pending_bps: u16,
@@ -24,12 +54,76 @@ pub struct ProtocolConfig {
     pub min_launch_quote_usdc_minor: u64,
     pub prediction_delegate: Pubkey,
     pub delegate_max_stake_usdc_minor: u64,
-    pub paused: bool,
+    pub reveal_timeout_seconds: i64,
+    pub min_artist_bond_usdc_minor: u64,
+    pub artist_bond_slash_bps: u16,
+    pub bond_withdrawal_cooldown_seconds: i64,
+    pub cancellation_fee_bps: u16,
+    /// Delay between proposing and executing a config change.
+    pub config_timelock_seconds: i64,
+    /// Quote token account that treasury withdrawals are paid to.
+    pub treasury_destination: Pubkey,
+    pub dust_destination: DustDestination,
+    /// Admin nominated by `propose_admin`; `Pubkey::default()` when none.
+    pub pending_admin: Pubkey,
+    /// `PAUSE_*` bits halted protocol-wide.
+    pub pause_flags: u8,
     pub bump: u8,
 }
 
 impl ProtocolConfig {
-    pub const LEN: usize = 8 + 32 + 32 + 2 + 2 + 2 + 2 + 8 + 8 + 8 + 32 + 8 + 1 + 1;
+    pub const LEN: usize = 8
+        + 32 * 6
+        + 32
+        + 2
+        + 2
+        + 2
+        + 2
+        + 8
+        + 8
+        + 8
+        + 32
+        + 8
+        + 8
+        + 8
+        + 2
+        + 8
+        + 2
+        + 8
+        + 32
+        + 32
+        + 1
+        + 1
+        + 1;
+}
+
+/// Protocol-owned quote account authority; platform fees are swept into its
+/// ATA and only leave towards `ProtocolConfig.treasury_destination`.
+#[account]
+pub struct ProtocolTreasury {
+    pub protocol: Pubkey,
+    pub total_swept_usdc_minor: u64,
+    pub total_withdrawn_usdc_minor: u64,
+    pub bump: u8,
+}
+
+impl ProtocolTreasury {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 1;
+}
+
+/// A config change waiting out `config_timelock_seconds`. At most one can be
+/// outstanding; cancel it to propose a different one.
+#[account]
+pub struct PendingProtocolConfig {
+    pub protocol: Pubkey,
+    pub params: UpdateProtocolConfigParams,
+    pub proposed_ts: i64,
+    pub executable_ts: i64,
+    pub bump: u8,
+}
+
+impl PendingProtocolConfig {
+    pub const LEN: usize = 8 + 32 + UpdateProtocolConfigParams::LEN + 8 + 8 + 1;
 }
 
 #[account]
@@ -42,11 +136,76 @@ pub struct Room {
     pub next_round_index: u64,
     pub pending_winner_rollover_usdc_minor: u64,
     pub pending_liquidity_rollover_usdc_minor: u64,
+    pub artist_bond_usdc_minor: u64,
+    pub bond_withdrawal_pending_usdc_minor: u64,
+    pub bond_withdrawal_requested_ts: i64,
+    pub bonded_rounds: u32,
+    pub operators: [Pubkey; MAX_ROOM_OPERATORS],
+    pub operator_count: u8,
+    pub market_mode: MarketMode,
+    pub lmsr_liquidity_usdc_minor: u64,
+    /// `PAUSE_*` bits halted for this room only, set by the protocol pauser.
+    pub pause_flags: u8,
+    /// Set while a `RoomFeeOverride` exists; `start_round` then requires it.
+    pub has_fee_override: bool,
+    /// Platform fees from settled rounds not yet swept to the treasury.
+    pub platform_fee_accrued_usdc_minor: u64,
+    /// Artist share of every settled round, including liquidity boosts.
+    pub artist_earned_usdc_minor: u64,
+    /// Total paid out by `claim_artist_pending`; never exceeds `artist_earned_usdc_minor`.
+    pub artist_claimed_usdc_minor: u64,
+    /// Sub-ledgers of the room escrow ATA: what each leg of the stake split
+    /// owes. Moved on every transfer in or out of the escrow and on internal
+    /// moves between legs; `reconcile_room_escrow` checks the balance
+    /// against their sum.
+    pub artist_pending_liability_usdc_minor: u64,
+    pub platform_fee_liability_usdc_minor: u64,
+    pub liquidity_reserve_liability_usdc_minor: u64,
+    pub winner_pot_liability_usdc_minor: u64,
     pub bump: u8,
 }
 
 impl Room {
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 12 + 32 + 8 + 8 + 8 + 1;
+    pub const LEN: usize = 8
+        + 32
+        + 32
+        + 8
+        + 12
+        + 32
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 4
+        + 32 * MAX_ROOM_OPERATORS
+        + 1
+        + 1
+        + 8
+        + 1
+        + 1
+        + 8
+        + 8
+        + 8
+        + 8 * 4
+        + 1;
+}
+
+/// Fee split approved by the fee manager for one room, replacing the
+/// protocol split from the room's next `start_round`.
+#[account]
+pub struct RoomFeeOverride {
+    pub room: Pubkey,
+    pub platform_fee_bps: u16,
+    pub artist_pending_bps: u16,
+    pub liquidity_reserve_bps: u16,
+    pub winner_pot_bps: u16,
+    pub bump: u8,
+}
+
+impl RoomFeeOverride {
+    pub const LEN: usize = 8 + 32 + 2 + 2 + 2 + 2 + 1;
 }
 
 #[account]
@@ -54,17 +213,75 @@ pub struct Round {
     pub room: Pubkey,
     pub index: u64,
     pub phase: RoundPhase,
+    pub market_mode: MarketMode,
     pub bpm: u16,
+    pub commit_version: u8,
     pub commit_hash: [u8; 32],
+    /// Positions ever placed, never decremented. Parimutuel placements land
+    /// here when their shard is folded, so the count lags while the round is
+    /// open. Cancelled positions are counted separately.
     pub total_predictions: u32,
+    pub cancelled_positions: u32,
+    /// Position accounts not yet closed; the round can only be closed at zero.
+    pub open_positions: u32,
+    /// `RoundShard` accounts opened for this round. Fixed once the round is
+    /// committed; counted back down as shards are closed.
+    pub shard_count: u8,
+    /// Economics copied from `ProtocolConfig` at `start_round`, so config
+    /// updates never change a round that is already running.
+    pub platform_fee_bps: u16,
+    pub artist_pending_bps: u16,
+    pub liquidity_reserve_bps: u16,
+    pub winner_pot_bps: u16,
+    pub dust_destination: DustDestination,
+    pub min_stake_usdc_minor: u64,
+    pub max_stake_usdc_minor: u64,
+    pub min_launch_quote_usdc_minor: u64,
+    pub cancellation_fee_bps: u16,
+    pub reveal_timeout_seconds: i64,
+    pub artist_bond_slash_bps: u16,
     pub total_staked_usdc_minor: u64,
+    /// Rounding dust routed to `dust_destination` by placements, including
+    /// positions later cancelled.
+    pub dust_usdc_minor: u64,
     pub artist_pending_usdc_minor: u64,
     pub platform_fee_usdc_minor: u64,
     pub liquidity_reserve_usdc_minor: u64,
     pub winner_pot_usdc_minor: u64,
     pub winner_pot_distributed_usdc_minor: u64,
+    /// Winner pot not attached to any tile (carried-in rollover), shared
+    /// pro-rata across all correct stake.
+    pub shared_winner_pot_usdc_minor: u64,
+    pub shared_winner_pot_distributed_usdc_minor: u64,
+    /// Per tile and side, indexed by `track_index * MAX_STEPS + step_index`.
+    /// Parimutuel rounds hold the winner-pot leg of every stake; LMSR rounds
+    /// hold outstanding shares, each paying one minor unit if it wins.
+    /// Readable during the open window so clients can show live odds.
+    pub tile_yes_pool_usdc_minor: [u64; TILE_COUNT],
+    pub tile_no_pool_usdc_minor: [u64; TILE_COUNT],
+    pub lmsr_liquidity_usdc_minor: u64,
+    pub lmsr_subsidy_usdc_minor: u64,
+    pub carried_winner_pot_usdc_minor: u64,
+    pub carried_liquidity_usdc_minor: u64,
+    /// Cancellation fees kept by `cancel_prediction`, per leg. Still counted
+    /// in the leg totals above; `cancel_round` releases them to the room,
+    /// since refunds only return the legs of positions still open.
+    pub cancellation_fee_artist_pending_usdc_minor: u64,
+    pub cancellation_fee_platform_usdc_minor: u64,
+    pub cancellation_fee_liquidity_reserve_usdc_minor: u64,
+    pub cancellation_fee_winner_pot_usdc_minor: u64,
+    pub bond_slash_usdc_minor: u64,
+    pub bond_slash_distributed_usdc_minor: u64,
+    pub prediction_close_ts: i64,
+    pub reveal_deadline_ts: i64,
     pub settled_positions: u32,
     pub winning_positions: u32,
+    pub tallied_positions: u32,
+    pub correct_positions: u32,
+    pub correct_stake_usdc_minor: u64,
+    /// Stake placed through the prediction delegate this round, capped at
+    /// `delegate_max_stake_usdc_minor`. Only the delegated path writes it;
+    /// those placements already serialize on the delegate payer.
     pub delegated_spent_usdc_minor: u64,
     pub outcome_bitmap: [u8; REVEAL_BITMAP_BYTES],
     pub reveal_verified: bool,
@@ -74,40 +291,155 @@ pub struct Round {
 impl Round {
     pub const LEN: usize = 8
         + 32
+        + 2
+        + 8
+        + 2
         + 8
+        + 8
+        + 1
         + 1
         + 2
+        + 1
         + 32
         + 4
+        + 4
+        + 4
+        + 1
+        + 2
+        + 2
+        + 2
+        + 2
+        + 1
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
         + 8
         + 8
         + 8
         + 8
         + 8
         + 8
+        + 8
+        + 8
+        + 8
+        + 4
+        + 4
         + 4
         + 4
         + 8
+        + 8
+        + 8
+        + 8
+        + 8 * TILE_COUNT
+        + 8 * TILE_COUNT
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
         + REVEAL_BITMAP_BYTES
         + 1
         + 1;
 }
 
+/// Write-side accumulator for one slice of a round's predictions. Parimutuel
+/// placements land on the shard picked by `round_shard_index` and escrow into
+/// its token account, so concurrent predictors never write-lock `Round` or the
+/// room escrow. `fold_round_shards` and `lock_round` move the totals and the
+/// escrowed stake over.
 #[account]
-pub struct PredictionPosition {
+pub struct RoundShard {
     pub round: Pubkey,
-    pub user: Pubkey,
+    pub index: u8,
+    /// Totals placed since the last fold.
+    pub predictions: u32,
+    pub total_staked_usdc_minor: u64,
+    pub artist_pending_usdc_minor: u64,
+    pub platform_fee_usdc_minor: u64,
+    pub liquidity_reserve_usdc_minor: u64,
+    pub winner_pot_usdc_minor: u64,
+    pub dust_usdc_minor: u64,
+    pub tile_yes_pool_usdc_minor: [u64; TILE_COUNT],
+    pub tile_no_pool_usdc_minor: [u64; TILE_COUNT],
+    pub bump: u8,
+    pub escrow_bump: u8,
+}
+
+impl RoundShard {
+    pub const LEN: usize =
+        8 + 32 + 1 + 4 + 8 + 8 + 8 + 8 + 8 + 8 + 8 * TILE_COUNT + 8 * TILE_COUNT + 1 + 1;
+}
+
+/// Outcome of `reconcile_room_escrow`, also set as its return data.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EscrowReconciliation {
+    pub room: Pubkey,
+    pub escrow_balance_usdc_minor: u64,
+    pub artist_pending_liability_usdc_minor: u64,
+    pub platform_fee_liability_usdc_minor: u64,
+    pub liquidity_reserve_liability_usdc_minor: u64,
+    pub winner_pot_liability_usdc_minor: u64,
+    pub shortfall_usdc_minor: u64,
+    pub surplus_usdc_minor: u64,
+    /// False when the escrow holds less than the liabilities booked on it.
+    pub solvent: bool,
+}
+
+/// One tile prediction inside a position.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct PositionEntry {
     pub track_index: u8,
     pub step_index: u8,
     pub will_be_active: bool,
     pub stake_amount_usdc_minor: u64,
+    pub winner_pot_usdc_minor: u64,
+    /// LMSR shares bought; zero for parimutuel entries.
+    pub shares: u64,
+    pub was_correct: bool,
+}
+
+impl PositionEntry {
+    pub const LEN: usize = 1 + 1 + 1 + 8 + 8 + 8 + 1;
+}
+
+/// A user's stake on one or more tiles of a round. Leg amounts are totals
+/// across all entries.
+#[account]
+pub struct PredictionPosition {
+    pub round: Pubkey,
+    pub user: Pubkey,
+    /// Client-chosen nonce the position PDA is seeded with.
+    pub client_nonce: u64,
+    pub stake_amount_usdc_minor: u64,
+    pub artist_pending_usdc_minor: u64,
+    pub platform_fee_usdc_minor: u64,
+    pub liquidity_reserve_usdc_minor: u64,
+    pub winner_pot_usdc_minor: u64,
+    /// Stake on entries that turned out correct, set by `tally_position`.
+    pub correct_stake_usdc_minor: u64,
+    /// True if any entry was correct.
     pub was_correct: bool,
+    pub tallied: bool,
     pub usdc_payout_usdc_minor: u64,
     pub settled: bool,
+    /// Set once `usdc_payout_usdc_minor` has left the escrow, by
+    /// `claim_winnings` or by a cancelled-round refund.
+    pub usdc_claimed: bool,
+    /// Reward-token claim flag.
     pub claimed: bool,
     pub bump: u8,
+    pub entries: Vec<PositionEntry>,
 }
 
 impl PredictionPosition {
-    pub const LEN: usize = 8 + 32 + 32 + 1 + 1 + 1 + 8 + 1 + 8 + 1 + 1 + 1;
+    pub const BASE_LEN: usize =
+        8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 8 + 1 + 1 + 1 + 1 + 4;
+
+    pub const fn space(entry_count: usize) -> usize {
+        Self::BASE_LEN + entry_count * PositionEntry::LEN
+    }
 }